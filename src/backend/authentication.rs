@@ -0,0 +1,329 @@
+use crate::backend_service::AuthenticatedUser;
+use crate::local_verification::{LocalVerifier, Verification};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::transport::Channel;
+use tonic::Status;
+use tower::{Layer, Service};
+use verifier_proto::token_verifier_client::TokenVerifierClient;
+use verifier_proto::{TokenIdRequest, TokenRequest};
+
+pub mod verifier_proto {
+    tonic::include_proto!("auction_house_rs.session.token_verifier");
+}
+
+const AUTH_HEADER: &str = "authorization";
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Backend methods which can be called without a token.
+const ANONYMOUS_METHODS: [&str; 3] = [
+    "/auction_house_rs.backend.Backend/ListAuctions",
+    "/auction_house_rs.backend.Backend/WatchAuctions",
+    "/auction_house_rs.backend.Backend/WatchAuctionChanges",
+];
+
+/// Layer verifying bearer tokens of incoming requests with the session's token verifier.
+///
+/// The name of the verified user is stored in the request's extensions as [`AuthenticatedUser`].
+#[derive(Clone)]
+pub struct AuthenticationLayer {
+    verifier: TokenVerifierClient<Channel>,
+    local_verifier: Option<Arc<LocalVerifier>>,
+}
+
+impl AuthenticationLayer {
+    pub fn new(verifier: TokenVerifierClient<Channel>) -> Self {
+        Self {
+            verifier,
+            local_verifier: None,
+        }
+    }
+
+    /// Verifies tokens locally when possible, the session is called only for tokens signed with unknown keys.
+    pub fn with_local_verifier(mut self, local_verifier: Arc<LocalVerifier>) -> Self {
+        self.local_verifier = Some(local_verifier);
+        self
+    }
+}
+
+impl<S> Layer<S> for AuthenticationLayer {
+    type Service = AuthenticationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthenticationService {
+            inner,
+            verifier: self.verifier.clone(),
+            local_verifier: self.local_verifier.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthenticationService<S> {
+    inner: S,
+    verifier: TokenVerifierClient<Channel>,
+    local_verifier: Option<Arc<LocalVerifier>>,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for AuthenticationService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // the inner service which has been polled for readiness has to be used to handle the request
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let mut verifier = self.verifier.clone();
+        let local_verifier = self.local_verifier.clone();
+        Box::pin(async move {
+            if !ANONYMOUS_METHODS.contains(&request.uri().path()) {
+                let result =
+                    authenticate(&mut verifier, local_verifier.as_deref(), request.headers()).await;
+                match result {
+                    Ok(user) => {
+                        request.extensions_mut().insert(AuthenticatedUser(user));
+                    }
+                    Err(status) => return Ok(status.to_http()),
+                }
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn get_bearer_token(headers: &http::HeaderMap) -> Result<&str, Status> {
+    headers
+        .get(AUTH_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+        .ok_or_else(|| {
+            Status::new(
+                tonic::Code::Unauthenticated,
+                "Failed to get token".to_string(),
+            )
+        })
+}
+
+/// Verifies the request's token and returns the name of its user.
+async fn authenticate(
+    verifier: &mut TokenVerifierClient<Channel>,
+    local_verifier: Option<&LocalVerifier>,
+    headers: &http::HeaderMap,
+) -> Result<String, Status> {
+    let token = get_bearer_token(headers)?;
+    if let Some(local_verifier) = local_verifier {
+        match local_verifier.verify(token) {
+            // revocations take effect at once, so they are checked with the session on every call
+            Verification::Verified { user, token_id } => {
+                let response = verifier
+                    .is_token_revoked(TokenIdRequest { token_id })
+                    .await?;
+                if response.into_inner().revoked {
+                    return Err(Status::new(
+                        tonic::Code::Unauthenticated,
+                        "Token has been revoked",
+                    ));
+                }
+                return Ok(user);
+            }
+            Verification::Rejected(status) => return Err(status),
+            Verification::UnknownKey => {}
+        }
+    }
+    let response = verifier
+        .verify_token(TokenRequest {
+            token: token.to_owned(),
+        })
+        .await?;
+    Ok(response.into_inner().username)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::local_verification::test::{public_key, sign, KEY_ID};
+    use argon2::password_hash::rand_core::OsRng;
+    use p256::ecdsa::SigningKey;
+    use std::collections::HashSet;
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+    use verifier_proto::token_verifier_server::{TokenVerifier, TokenVerifierServer};
+    use verifier_proto::{
+        PublicKey, PublicKeysResponse, TokenRevokedResponse, VerifyTokenResponse,
+    };
+
+    fn create_service(
+    ) -> impl Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible>
+    {
+        let channel = Channel::from_static("http://[::1]:1").connect_lazy();
+        let inner = tower::service_fn(|request: http::Request<()>| async move {
+            let mut response = http::Response::new(tonic::body::empty_body());
+            if let Some(AuthenticatedUser(user)) = request.extensions().get() {
+                response.headers_mut().insert("user", user.parse().unwrap());
+            }
+            Ok::<_, Infallible>(response)
+        });
+        AuthenticationLayer::new(TokenVerifierClient::new(channel)).layer(inner)
+    }
+
+    fn grpc_status(response: &http::Response<BoxBody>) -> Option<tonic::Code> {
+        Status::from_header_map(response.headers()).map(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_method_does_not_require_token() {
+        let mut service = create_service();
+        let request = http::Request::builder()
+            .uri("/auction_house_rs.backend.Backend/ListAuctions")
+            .body(())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(grpc_status(&response), None);
+        assert!(response.headers().get("user").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_watch_auction_changes_does_not_require_token() {
+        let mut service = create_service();
+        let request = http::Request::builder()
+            .uri("/auction_house_rs.backend.Backend/WatchAuctionChanges")
+            .body(())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(grpc_status(&response), None);
+        assert!(response.headers().get("user").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_method_without_token_is_rejected() {
+        let mut service = create_service();
+        let request = http::Request::builder()
+            .uri("/auction_house_rs.backend.Backend/DepositFunds")
+            .body(())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(grpc_status(&response), Some(tonic::Code::Unauthenticated));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_token_is_rejected_by_local_verifier() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(AUTH_HEADER, "Bearer invalid".parse().unwrap());
+        let channel = Channel::from_static("http://[::1]:1").connect_lazy();
+        let local_verifier = LocalVerifier::new(std::time::Duration::from_secs(60));
+        let result = authenticate(
+            &mut TokenVerifierClient::new(channel),
+            Some(&local_verifier),
+            &headers,
+        )
+        .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    /// Session's token verifier, which knows public keys and revoked tokens, but verifies no tokens.
+    struct TestVerifier {
+        keys: Vec<PublicKey>,
+        revoked: Arc<Mutex<HashSet<String>>>,
+    }
+
+    #[tonic::async_trait]
+    impl TokenVerifier for TestVerifier {
+        async fn verify_token(
+            &self,
+            _request: tonic::Request<TokenRequest>,
+        ) -> Result<tonic::Response<VerifyTokenResponse>, Status> {
+            Err(Status::new(tonic::Code::Unimplemented, "Not verified"))
+        }
+
+        async fn get_public_keys(
+            &self,
+            _request: tonic::Request<()>,
+        ) -> Result<tonic::Response<PublicKeysResponse>, Status> {
+            Ok(tonic::Response::new(PublicKeysResponse {
+                keys: self.keys.clone(),
+            }))
+        }
+
+        async fn is_token_revoked(
+            &self,
+            request: tonic::Request<TokenIdRequest>,
+        ) -> Result<tonic::Response<TokenRevokedResponse>, Status> {
+            let token_id = request.into_inner().token_id;
+            let revoked = self.revoked.lock().unwrap().contains(&token_id);
+            Ok(tonic::Response::new(TokenRevokedResponse { revoked }))
+        }
+    }
+
+    /// Serves the verifier on a free local port and returns a client connected to it.
+    async fn serve(verifier: TestVerifier) -> TokenVerifierClient<Channel> {
+        let address = std::net::TcpListener::bind("[::1]:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(TokenVerifierServer::new(verifier))
+                .serve(address),
+        );
+        let endpoint = Channel::from_shared(format!("http://{}", address)).unwrap();
+        loop {
+            if let Ok(channel) = endpoint.connect().await {
+                return TokenVerifierClient::new(channel);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_locally_verified_token_is_rejected_right_after_revocation() {
+        let key = SigningKey::random(&mut OsRng);
+        let revoked = Arc::new(Mutex::new(HashSet::new()));
+        let mut client = serve(TestVerifier {
+            keys: vec![public_key(&key)],
+            revoked: revoked.clone(),
+        })
+        .await;
+        let local_verifier = LocalVerifier::new(std::time::Duration::from_secs(60));
+        local_verifier.refresh(&mut client).await.unwrap();
+        let mut headers = http::HeaderMap::new();
+        let token = sign(&key, KEY_ID, "id", u64::MAX);
+        let value = format!("{}{}", BEARER_PREFIX, token);
+        headers.insert(AUTH_HEADER, value.parse().unwrap());
+        let user = authenticate(&mut client, Some(&local_verifier), &headers)
+            .await
+            .unwrap();
+        assert_eq!(user, "user");
+        // the user logs out, the token is revoked without refreshing the local verifier
+        revoked.lock().unwrap().insert("id".to_string());
+        let result = authenticate(&mut client, Some(&local_verifier), &headers).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn test_get_bearer_token() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(AUTH_HEADER, "Bearer token".parse().unwrap());
+        assert_eq!(get_bearer_token(&headers).unwrap(), "token");
+    }
+
+    #[test]
+    fn test_get_bearer_token_without_bearer_prefix() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(AUTH_HEADER, "token".parse().unwrap());
+        assert!(get_bearer_token(&headers).is_err());
+    }
+}
@@ -1,18 +1,28 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 pub mod auctions_memory_storage;
+pub mod auctions_mongo_storage;
+pub mod mongo;
 pub mod users_memory_storage;
+pub mod users_mongo_storage;
 
-type Funds = u32;
+pub type Funds = u32;
+
+/// Opaque identifier of an auction, ULIDs are unique and ordered by the time of their creation.
+pub type AuctionId = ulid::Ulid;
 
 /// Trait for user data storage.
-pub trait UsersBackend {
+///
+/// Implementations are shared between requests, so they have to synchronize access to the data internally.
+#[tonic::async_trait]
+pub trait UsersBackend: Send + Sync {
     /// Adds a new user with 0 funds and 0 items.
     ///
     /// # Arguments
     /// * `user` - The user's name.
     /// # Returns
     /// Should return an error if the user already exists.
-    fn add_user(&mut self, user: &str) -> Result<(), Box<dyn std::error::Error>>;
+    async fn add_user(&self, user: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
     /// Deposits funds to the user's account.
     ///
@@ -21,11 +31,11 @@ pub trait UsersBackend {
     /// * `amount` - The amount of funds to deposit.
     /// # Returns
     ///  Should return an error if the user does not exist or max funds exceeded.
-    fn deposit_funds(
-        &mut self,
+    async fn deposit_funds(
+        &self,
         user: &str,
         amount: Funds,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
     /// Withdraws funds from the user's account.
     ///
@@ -34,11 +44,11 @@ pub trait UsersBackend {
     /// * `amount` - The amount of funds to withdraw.
     /// # Returns
     /// Should return an error if the user does not exist or insufficient funds.
-    fn withdraw_funds(
-        &mut self,
+    async fn withdraw_funds(
+        &self,
         user: &str,
         amount: Funds,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
     /// Deposits an item to the user's account.
     ///
@@ -47,7 +57,11 @@ pub trait UsersBackend {
     /// * `item` - The item's name.
     /// # Returns
     /// Should return an error if the user does not exist or item already exists.
-    fn deposit_item(&mut self, user: &str, item: &str) -> Result<(), Box<dyn std::error::Error>>;
+    async fn deposit_item(
+        &self,
+        user: &str,
+        item: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
     /// Withdraws an item from the user's account.
     ///
@@ -56,7 +70,11 @@ pub trait UsersBackend {
     /// * `item` - The item's name.
     /// # Returns
     /// Should return an error if the user does not exist or item does not exist.
-    fn withdraw_item(&mut self, user: &str, item: &str) -> Result<(), Box<dyn std::error::Error>>;
+    async fn withdraw_item(
+        &self,
+        user: &str,
+        item: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
     /// Lists the user's items.
     ///
@@ -64,15 +82,200 @@ pub trait UsersBackend {
     /// * `user` - The user's name.
     /// # Returns
     /// Should return a vector of the user's items or an error if the user does not exist.
-    fn list_items(&self, user: &str) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+    async fn list_items(
+        &self,
+        user: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>>;
 
     /// Show the user's funds.
     ///
     /// # Arguments
     /// * `user` - The user's name.
     /// # Returns
-    /// Should return user's funds or an error if the user does not exist.
-    fn show_funds(&self, user: &str) -> Result<u32, Box<dyn std::error::Error>>;
+    /// Should return user's available funds, without held funds, or an error if the user does not exist.
+    async fn show_funds(&self, user: &str)
+        -> Result<u32, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Moves funds from the user's available funds to held funds, which cannot be withdrawn.
+    ///
+    /// # Arguments
+    /// * `user` - The user's name.
+    /// * `amount` - The amount of funds to hold.
+    /// # Returns
+    /// Should return an error if the user does not exist or insufficient funds.
+    async fn hold_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Moves funds from the user's held funds back to available funds.
+    ///
+    /// # Arguments
+    /// * `user` - The user's name.
+    /// * `amount` - The amount of funds to release.
+    /// # Returns
+    /// Should return an error if the user does not exist or insufficient held funds.
+    async fn release_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Show the user's held funds.
+    ///
+    /// # Arguments
+    /// * `user` - The user's name.
+    /// # Returns
+    /// Should return user's held funds or an error if the user does not exist.
+    async fn show_held_funds(
+        &self,
+        user: &str,
+    ) -> Result<Funds, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Moves funds from the user's held funds back to available funds as a step of a settlement,
+    /// the step is applied at most once, so repeating it has no effect.
+    ///
+    /// # Arguments
+    /// * `user` - The user's name.
+    /// * `amount` - The amount of funds to release.
+    /// * `step` - The step's id, unique among the user's settlement steps.
+    /// # Returns
+    /// Should return the same as [`UsersBackend::release_funds`], or nothing if the step has already been applied.
+    async fn release_funds_once(
+        &self,
+        user: &str,
+        amount: Funds,
+        step: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Adds an item to the user's inventory as a step of a settlement,
+    /// the step is applied at most once, so repeating it has no effect.
+    ///
+    /// # Arguments
+    /// * `user` - The user's name.
+    /// * `item` - The item's name.
+    /// * `step` - The step's id, unique among the user's settlement steps.
+    /// # Returns
+    /// Should return the same as [`UsersBackend::deposit_item`], or nothing if the step has already been applied.
+    async fn deposit_item_once(
+        &self,
+        user: &str,
+        item: &str,
+        step: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Pays the seller with the buyer's held funds and moves the item to the buyer's inventory,
+    /// either all of it is applied or nothing. The sale is applied at most once, repeating it
+    /// returns the same result without changing anything.
+    ///
+    /// # Arguments
+    /// * `seller` - The seller's name.
+    /// * `buyer` - The buyer's name.
+    /// * `item` - The item's name.
+    /// * `price` - The amount of the buyer's held funds paid to the seller.
+    /// * `settlement` - The settlement's id, unique among the users' settlements.
+    /// # Returns
+    /// Should return an error if any of the users does not exist, the buyer has insufficient held funds, the buyer already has the item, the seller's max funds exceeded or the sale has already failed.
+    async fn settle_sale(
+        &self,
+        seller: &str,
+        buyer: &str,
+        item: &str,
+        price: Funds,
+        settlement: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// The highest bid of an auction.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bid {
+    pub bidder: String,
+    pub amount: Funds,
+}
+
+/// Result of a bid which has become the highest bid or has been sealed, or of buying the item at the buy now price.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlacedBid {
+    /// The auction after the bid or the purchase.
+    pub auction: Auction,
+    /// The bid which is no longer the highest with the amount held for it, or the sealed bid
+    /// which the placed one has replaced, if any.
+    pub outbid: Option<Bid>,
+    /// Whether the bid has extended the auction's end time.
+    pub extended: bool,
+}
+
+/// How much a bid has to exceed the current highest bid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum BidIncrement {
+    /// A fixed amount of funds.
+    Amount(Funds),
+    /// A percentage of the current highest bid, rounded up.
+    Percent(u32),
+}
+
+impl Default for BidIncrement {
+    fn default() -> Self {
+        Self::Amount(1)
+    }
+}
+
+impl BidIncrement {
+    /// Returns the increment over the given price, a bid always has to exceed the price by at least 1.
+    fn over(self, price: Funds) -> Funds {
+        let increment = match self {
+            Self::Amount(amount) => amount,
+            Self::Percent(percent) => {
+                let increment = (u64::from(price) * u64::from(percent)).div_ceil(100);
+                Funds::try_from(increment).unwrap_or(Funds::MAX)
+            }
+        };
+        increment.max(1)
+    }
+}
+
+/// How the price of an auction is set.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AuctionType {
+    /// Bidders raise the price from the starting price, the highest bid wins at the end.
+    #[default]
+    English,
+    /// The price drops from the starting price on the schedule, the first bid at the current price wins at once.
+    Dutch(PriceSchedule),
+    /// Bids stay sealed until the end, the highest bid wins and pays its own amount.
+    SealedFirstPrice,
+    /// Bids stay sealed until the end, the highest bid wins and pays the second highest bid (Vickrey auction).
+    SealedSecondPrice,
+}
+
+/// Schedule of the price of a Dutch auction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriceSchedule {
+    /// The lowest price, the price never drops below it.
+    pub floor_price: Funds,
+    /// Drops of the price, which stop at the floor price and may not reach it before the end time,
+    /// without them the price drops steadily to the floor price at the end time.
+    pub step: Option<PriceStep>,
+}
+
+/// A drop of the price by `amount` every `interval`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriceStep {
+    pub amount: Funds,
+    pub interval: std::time::Duration,
+}
+
+/// Extension of an auction by bids placed shortly before its end.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SoftClose {
+    /// Bids placed within this time before the end extend the auction.
+    pub window: std::time::Duration,
+    /// How far each such bid pushes the end out.
+    pub extension: std::time::Duration,
+    /// The auction is never extended past this time, if any.
+    pub max_end_time: Option<std::time::SystemTime>,
 }
 
 /// A struct representing an auction.
@@ -81,42 +284,473 @@ pub struct Auction {
     item: String,
     starting_price: Funds,
     current_price: Funds,
+    min_increment: BidIncrement,
+    reserve_price: Option<Funds>,
+    buy_now_price: Option<Funds>,
+    soft_close: Option<SoftClose>,
+    auction_type: AuctionType,
     start_time: std::time::SystemTime,
     end_time: std::time::SystemTime,
     seller: String,
     buyer: Option<String>,
+    /// The buyer's hidden maximum bid, which is held in escrow.
+    max_bid: Option<Funds>,
+    /// Bids of a sealed-bid auction in the order they have been placed, each is held in escrow.
+    sealed_bids: Vec<Bid>,
+    /// Number of bids which have changed the auction, it orders the auction's updates.
+    version: u64,
+    /// Everyone who has bid on the auction, in the order of their first bids.
+    bidders: Vec<String>,
 }
 
 impl Auction {
-    fn new(item: &str, starting_price: Funds, duration: std::time::Duration, seller: &str) -> Self {
+    pub fn new(
+        item: &str,
+        starting_price: Funds,
+        duration: std::time::Duration,
+        seller: &str,
+    ) -> Self {
         let start_time = std::time::SystemTime::now();
         let end_time = start_time + duration;
         Self {
             item: item.to_owned(),
             starting_price,
             current_price: 0,
+            min_increment: BidIncrement::default(),
+            reserve_price: None,
+            buy_now_price: None,
+            soft_close: None,
+            auction_type: AuctionType::English,
             start_time,
             end_time,
             seller: seller.to_owned(),
             buyer: None,
+            max_bid: None,
+            sealed_bids: Vec::new(),
+            version: 0,
+            bidders: Vec::new(),
+        }
+    }
+
+    /// Sets the minimum increment of bids following the opening bid.
+    pub fn with_min_increment(mut self, min_increment: BidIncrement) -> Self {
+        self.min_increment = min_increment;
+        self
+    }
+
+    /// Sets the hidden price below which the item is not sold.
+    pub fn with_reserve_price(mut self, reserve_price: Funds) -> Self {
+        self.reserve_price = Some(reserve_price);
+        self
+    }
+
+    /// Sets the price for which the item can be bought at once, ending the auction.
+    pub fn with_buy_now_price(mut self, buy_now_price: Funds) -> Self {
+        self.buy_now_price = Some(buy_now_price);
+        self
+    }
+
+    /// Makes bids placed within `window` before the end push the end out by `extension`,
+    /// in total by at most `max_extension` if it is given.
+    pub fn with_soft_close(
+        mut self,
+        window: std::time::Duration,
+        extension: std::time::Duration,
+        max_extension: Option<std::time::Duration>,
+    ) -> Self {
+        self.soft_close = Some(SoftClose {
+            window,
+            extension,
+            // an end time which cannot be represented does not limit the extensions
+            max_end_time: max_extension
+                .and_then(|max_extension| self.end_time.checked_add(max_extension)),
+        });
+        self
+    }
+
+    /// Sets the type of the auction, which is English by default.
+    pub fn with_type(mut self, auction_type: AuctionType) -> Self {
+        self.auction_type = auction_type;
+        self
+    }
+
+    pub fn item(&self) -> &str {
+        &self.item
+    }
+
+    pub fn starting_price(&self) -> Funds {
+        self.starting_price
+    }
+
+    pub fn current_price(&self) -> Funds {
+        self.current_price
+    }
+
+    pub fn reserve_price(&self) -> Option<Funds> {
+        self.reserve_price
+    }
+
+    /// Returns whether the highest bid reaches the reserve price, always true without a reserve price.
+    pub fn reserve_met(&self) -> bool {
+        match self.reserve_price {
+            Some(reserve_price) => self.buyer.is_some() && self.current_price >= reserve_price,
+            None => true,
+        }
+    }
+
+    /// Returns the buy now price while it is offered, it is no longer offered once a bid exceeds it.
+    pub fn buy_now_price(&self) -> Option<Funds> {
+        self.buy_now_price
+            .filter(|&buy_now_price| self.buyer.is_none() || self.current_price <= buy_now_price)
+    }
+
+    /// Returns the end time after a bid placed at the given time, which is extended by the soft close.
+    pub fn extended_end_time(&self, bid_time: std::time::SystemTime) -> std::time::SystemTime {
+        let Some(soft_close) = self.soft_close else {
+            return self.end_time;
+        };
+        // a window which cannot be added to the bid time covers the whole auction
+        if bid_time
+            .checked_add(soft_close.window)
+            .is_some_and(|window_end| window_end < self.end_time)
+        {
+            return self.end_time;
         }
+        // an extension which cannot be represented reaches the latest end time, if any
+        match (
+            self.end_time.checked_add(soft_close.extension),
+            soft_close.max_end_time,
+        ) {
+            (Some(end_time), Some(max_end_time)) => end_time.min(max_end_time).max(self.end_time),
+            (None, Some(max_end_time)) => max_end_time.max(self.end_time),
+            (Some(end_time), None) => end_time,
+            (None, None) => self.end_time,
+        }
+    }
+
+    pub fn auction_type(&self) -> AuctionType {
+        self.auction_type
+    }
+
+    /// Returns whether bids stay sealed until the auction ends.
+    pub fn is_sealed(&self) -> bool {
+        matches!(
+            self.auction_type,
+            AuctionType::SealedFirstPrice | AuctionType::SealedSecondPrice
+        )
+    }
+
+    /// Returns the version of the auction, it increases with every bid, so of two states of the auction
+    /// the one with the higher version is the later one.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns whether the user sells the auction, has bid on it or has bought it.
+    pub fn involves(&self, user: &str) -> bool {
+        self.seller == user
+            || self.buyer.as_deref() == Some(user)
+            || self.bidders.iter().any(|bidder| bidder == user)
+    }
+
+    /// Returns the number of sealed bids, one for each bidder.
+    pub fn bid_count(&self) -> usize {
+        self.sealed_bids.len()
+    }
+
+    /// Returns the price of a Dutch auction at the given time, the starting price for other auctions.
+    pub fn price_at(&self, time: std::time::SystemTime) -> Funds {
+        let AuctionType::Dutch(schedule) = self.auction_type else {
+            return self.starting_price;
+        };
+        let elapsed = time.duration_since(self.start_time).unwrap_or_default();
+        let price = match schedule.step {
+            Some(step) => {
+                let steps = elapsed.as_millis() / step.interval.as_millis().max(1);
+                let drop = steps.saturating_mul(u128::from(step.amount));
+                u128::from(self.starting_price).saturating_sub(drop)
+            }
+            None => {
+                let duration = self
+                    .end_time
+                    .duration_since(self.start_time)
+                    .unwrap_or_default()
+                    .as_millis()
+                    .max(1);
+                let range = u128::from(self.starting_price.saturating_sub(schedule.floor_price));
+                let drop = range * elapsed.as_millis().min(duration) / duration;
+                u128::from(self.starting_price) - drop
+            }
+        };
+        Funds::try_from(price)
+            .unwrap_or(Funds::MAX)
+            .max(schedule.floor_price)
+    }
+
+    /// Returns the lowest acceptable bid, the starting price for the opening bid,
+    /// otherwise the current price raised by the minimum increment.
+    /// The current price of a Dutch auction is the only acceptable bid.
+    pub fn min_bid(&self) -> Funds {
+        if let AuctionType::Dutch(_) = self.auction_type {
+            return self.price_at(std::time::SystemTime::now());
+        }
+        match self.buyer {
+            Some(_) => self
+                .current_price
+                .saturating_add(self.min_increment.over(self.current_price)),
+            None => self.starting_price,
+        }
+    }
+
+    pub fn start_time(&self) -> std::time::SystemTime {
+        self.start_time
+    }
+
+    pub fn end_time(&self) -> std::time::SystemTime {
+        self.end_time
+    }
+
+    pub fn seller(&self) -> &str {
+        &self.seller
+    }
+
+    pub fn buyer(&self) -> Option<&str> {
+        self.buyer.as_deref()
+    }
+
+    /// Returns the buyer with the amount held for their bid, which is their maximum bid.
+    pub fn held_bid(&self) -> Option<Bid> {
+        self.buyer.as_ref().map(|buyer| Bid {
+            bidder: buyer.clone(),
+            amount: self.max_bid.unwrap_or(self.current_price),
+        })
+    }
+
+    /// Returns all bids which are held in escrow, the sealed bids or the highest bid.
+    pub fn held_bids(&self) -> Vec<Bid> {
+        match self.held_bid() {
+            Some(held) => vec![held],
+            None => self.sealed_bids.clone(),
+        }
+    }
+
+    /// Opens the sealed bids, the highest one becomes the auction's highest bid, earlier of equal ones.
+    /// It is priced at its amount or, in a second-price auction, at the second highest bid,
+    /// the starting price without one, but at least at the reserve price if the bid reaches it.
+    /// # Returns
+    /// The losing bids, whose funds are to be released.
+    pub fn unseal(&mut self) -> Vec<Bid> {
+        let mut bids = std::mem::take(&mut self.sealed_bids);
+        let highest = (0..bids.len()).reduce(|highest, index| {
+            if bids[index].amount > bids[highest].amount {
+                index
+            } else {
+                highest
+            }
+        });
+        let Some(highest) = highest else {
+            return bids;
+        };
+        let winner = bids.remove(highest);
+        self.current_price = match self.auction_type {
+            AuctionType::SealedSecondPrice => bids
+                .iter()
+                .map(|bid| bid.amount)
+                .max()
+                .unwrap_or(self.starting_price)
+                .max(self.reserve_price.unwrap_or_default())
+                .min(winner.amount),
+            _ => winner.amount,
+        };
+        self.buyer = Some(winner.bidder);
+        self.max_bid = Some(winner.amount);
+        bids
+    }
+
+    /// Places a bid of `max_amount`, either a plain bid or a proxy bid, for which the house bids
+    /// on the bidder's behalf in minimum increments up to `max_amount`.
+    ///
+    /// The highest maximum bid wins, earlier of equal ones, and its price is raised only as far
+    /// as needed to beat the other one, or to the reserve price if its maximum reaches it.
+    /// A plain bid is always priced at its amount.
+    /// # Returns
+    /// The auction after the bid with the bid which is no longer the highest, which may be the placed one,
+    /// or an error if the bid is not acceptable, in which case the auction is unchanged.
+    pub fn place_bid(
+        &mut self,
+        bidder: &str,
+        max_amount: Funds,
+        proxy: bool,
+        bid_time: std::time::SystemTime,
+    ) -> Result<PlacedBid, Box<dyn std::error::Error + Send + Sync>> {
+        if self.seller == bidder {
+            return Err("Seller cannot bid on their own auction".into());
+        }
+        if let AuctionType::Dutch(_) = self.auction_type {
+            return self.accept_dutch_bid(bidder, max_amount, proxy, bid_time);
+        }
+        if self.is_sealed() {
+            return self.accept_sealed_bid(bidder, max_amount, proxy, bid_time);
+        }
+        if self.buyer.as_deref() == Some(bidder) {
+            return Err("Bidder is already the highest bidder".into());
+        }
+        let min_bid = self.min_bid();
+        if max_amount < min_bid {
+            return Err(format!("Bid amount is lower than the minimum bid of {}", min_bid).into());
+        }
+        if self.end_time < bid_time {
+            return Err("Auction is already concluded".into());
+        }
+        let outbid = match self.held_bid() {
+            None => {
+                self.current_price = if proxy {
+                    self.proxy_price(min_bid, max_amount)
+                } else {
+                    max_amount
+                };
+                self.buyer = Some(bidder.to_owned());
+                self.max_bid = Some(max_amount);
+                None
+            }
+            Some(highest) if max_amount > highest.amount => {
+                self.current_price = if proxy {
+                    let price = highest
+                        .amount
+                        .saturating_add(self.min_increment.over(highest.amount));
+                    self.proxy_price(price.min(max_amount).max(min_bid), max_amount)
+                } else {
+                    max_amount
+                };
+                self.buyer = Some(bidder.to_owned());
+                self.max_bid = Some(max_amount);
+                Some(highest)
+            }
+            // the highest bidder's proxy outbids the bid at once
+            Some(highest) => {
+                let price = max_amount
+                    .saturating_add(self.min_increment.over(max_amount))
+                    .min(highest.amount);
+                self.current_price = self.proxy_price(price, highest.amount);
+                Some(Bid {
+                    bidder: bidder.to_owned(),
+                    amount: max_amount,
+                })
+            }
+        };
+        let end_time = self.extended_end_time(bid_time);
+        let extended = end_time != self.end_time;
+        self.end_time = end_time;
+        self.record_bid(bidder);
+        Ok(PlacedBid {
+            auction: self.clone(),
+            outbid,
+            extended,
+        })
+    }
+
+    /// Raises the price of a proxy bid to the reserve price if its maximum reaches it,
+    /// otherwise the maximum stays hidden.
+    fn proxy_price(&self, price: Funds, max_amount: Funds) -> Funds {
+        match self.reserve_price {
+            Some(reserve_price) if max_amount >= reserve_price => price.max(reserve_price),
+            _ => price,
+        }
+    }
+
+    /// Records an accepted bid, whether it has become the highest or not.
+    fn record_bid(&mut self, bidder: &str) {
+        self.version += 1;
+        if !self.bidders.iter().any(|known| known == bidder) {
+            self.bidders.push(bidder.to_owned());
+        }
+    }
+
+    /// The first bid at the current price wins at once, the bidder pays the price and the auction ends.
+    fn accept_dutch_bid(
+        &mut self,
+        bidder: &str,
+        amount: Funds,
+        proxy: bool,
+        bid_time: std::time::SystemTime,
+    ) -> Result<PlacedBid, Box<dyn std::error::Error + Send + Sync>> {
+        if proxy {
+            return Err("Proxy bids are not accepted in Dutch auctions".into());
+        }
+        if self.buyer.is_some() || self.end_time < bid_time {
+            return Err("Auction is already concluded".into());
+        }
+        let price = self.price_at(bid_time);
+        if amount < price {
+            return Err(format!("Bid amount is lower than the current price of {}", price).into());
+        }
+        self.buyer = Some(bidder.to_owned());
+        self.current_price = price;
+        self.max_bid = Some(amount);
+        self.end_time = bid_time;
+        self.record_bid(bidder);
+        Ok(PlacedBid {
+            auction: self.clone(),
+            outbid: None,
+            extended: false,
+        })
+    }
+
+    /// The bid is sealed until the end, it replaces the bidder's previous sealed bid,
+    /// which loses its place among equal bids.
+    fn accept_sealed_bid(
+        &mut self,
+        bidder: &str,
+        amount: Funds,
+        proxy: bool,
+        bid_time: std::time::SystemTime,
+    ) -> Result<PlacedBid, Box<dyn std::error::Error + Send + Sync>> {
+        if proxy {
+            return Err("Proxy bids are not accepted in sealed-bid auctions".into());
+        }
+        if amount < self.starting_price {
+            return Err(format!(
+                "Bid amount is lower than the minimum bid of {}",
+                self.starting_price
+            )
+            .into());
+        }
+        if self.end_time < bid_time {
+            return Err("Auction is already concluded".into());
+        }
+        let replaced = self
+            .sealed_bids
+            .iter()
+            .position(|bid| bid.bidder == bidder)
+            .map(|index| self.sealed_bids.remove(index));
+        self.sealed_bids.push(Bid {
+            bidder: bidder.to_owned(),
+            amount,
+        });
+        self.record_bid(bidder);
+        Ok(PlacedBid {
+            auction: self.clone(),
+            outbid: replaced,
+            extended: false,
+        })
     }
 }
 
 /// Trait for auctions data storage.
-pub trait AuctionsBackend {
-    type AuctionId;
-
+///
+/// Implementations are shared between requests, so they have to synchronize access to the data internally.
+#[tonic::async_trait]
+pub trait AuctionsBackend: Send + Sync {
     /// Adds a new auction.
     ///
     /// # Arguments
     /// * `auction` - The auction to add.
     /// # Returns
     /// Should return an auction id or an error if adding the auction failed.
-    fn add_auction(
-        &mut self,
+    async fn add_auction(
+        &self,
         auction: Auction,
-    ) -> Result<Self::AuctionId, Box<dyn std::error::Error>>;
+    ) -> Result<AuctionId, Box<dyn std::error::Error + Send + Sync>>;
 
     /// Bids on an auction.
     ///
@@ -125,36 +759,97 @@ pub trait AuctionsBackend {
     /// * `bidder` - The bidder's name.
     /// * `amount` - The amount of funds to bid.
     /// # Returns
-    /// Should return an error if the auction does not exist, the auction is concluded, the bidder does not exist, the bidder is the seller, the bidder is the current highest bidder, or the bid amount is lower than the current price.
-    fn bid_auction(
-        &mut self,
-        auction_id: Self::AuctionId,
+    /// Should return the auction after the bid, whose end time is extended if the bid falls within its soft close window, with the outbid bid, if any, which is the placed one if the highest bidder's proxy bid is at least as high, or an error if the auction does not exist, the auction is concluded, the bidder does not exist, the bidder is the seller, the bidder is the current highest bidder, or the bid amount is lower than the minimum bid. A bid on a sealed-bid auction is only recorded, the outbid bid is the bidder's previous sealed bid which it replaces, if any.
+    async fn bid_auction(
+        &self,
+        auction_id: AuctionId,
         bidder: &str,
         amount: Funds,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<PlacedBid, Box<dyn std::error::Error + Send + Sync>>;
 
-    /// Close an auction and return the auction's information.
+    /// Places a proxy bid, the house bids on the bidder's behalf in minimum increments up to `max_amount`,
+    /// which stays hidden. See [`Auction::place_bid`] for how competing bids are resolved.
     ///
     /// # Arguments
     /// * `auction_id` - The auction's id.
+    /// * `bidder` - The bidder's name.
+    /// * `max_amount` - The maximum amount of funds to bid.
     /// # Returns
-    /// Should return an auction or an error if the auction does not exist or the auction is already concluded.
-    fn close_auction(
-        &mut self,
-        auction_id: Self::AuctionId,
-    ) -> Result<Auction, Box<dyn std::error::Error>>;
+    /// Should return the same as [`AuctionsBackend::bid_auction`], the outbid bid is the placed one if another proxy bid is at least as high.
+    async fn proxy_bid_auction(
+        &self,
+        auction_id: AuctionId,
+        bidder: &str,
+        max_amount: Funds,
+    ) -> Result<PlacedBid, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Returns an auction.
+    ///
+    /// # Arguments
+    /// * `auction_id` - The auction's id.
+    /// # Returns
+    /// Should return the auction or an error if the auction does not exist.
+    async fn get_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<Auction, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Buys the auctioned item for the buy now price, which ends the auction and marks it as being settled.
+    ///
+    /// # Arguments
+    /// * `auction_id` - The auction's id.
+    /// * `buyer` - The buyer's name.
+    /// # Returns
+    /// Should return the ended auction with the buyer as the highest bidder at the buy now price together with the outbid highest bid, if any, or an error if the auction does not exist, the auction is concluded, the buyer is the seller or buy now is not offered.
+    async fn buy_now(
+        &self,
+        auction_id: AuctionId,
+        buyer: &str,
+    ) -> Result<PlacedBid, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Close an auction before it ends and return the auction's information.
+    ///
+    /// # Arguments
+    /// * `auction_id` - The auction's id.
+    /// * `seller` - The name of the user closing the auction.
+    /// # Returns
+    /// Should return an auction or an error if the auction does not exist, the user is not the seller or the auction is already concluded.
+    async fn close_auction(
+        &self,
+        auction_id: AuctionId,
+        seller: &str,
+    ) -> Result<Auction, Box<dyn std::error::Error + Send + Sync>>;
 
     /// Lists all ongoing auctions.
     /// # Returns
     /// Should return a vector of all ongoing auctions with their ids or an error if listing the auctions failed.
-    fn list_ongoing_auctions(
+    async fn list_ongoing_auctions(
         &self,
-    ) -> Result<HashMap<Self::AuctionId, Auction>, Box<dyn std::error::Error>>;
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn std::error::Error + Send + Sync>>;
 
-    /// Removes all concluded auctions from the storage and returns them.
+    /// Marks concluded auctions as being settled and returns them, each auction is claimed
+    /// by exactly one caller. The auctions stay in the storage until they are removed as settled.
     /// # Returns
-    /// Should return a vector of all concluded auctions with their ids or an error if listing the auctions failed.
-    fn pop_concluded_auctions(
-        &mut self,
-    ) -> Result<HashMap<Self::AuctionId, Auction>, Box<dyn std::error::Error>>;
+    /// Should return the claimed auctions with their ids or an error if claiming the auctions failed.
+    async fn claim_concluded_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Lists auctions which are being settled, including the ones whose settlement has been interrupted.
+    /// # Returns
+    /// Should return the auctions with their ids or an error if listing the auctions failed.
+    async fn list_settling_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Removes an auction whose settlement has been completed.
+    ///
+    /// # Arguments
+    /// * `auction_id` - The auction's id.
+    /// # Returns
+    /// Should return an error if removing the auction failed, removing an auction which does not exist is not an error.
+    async fn remove_settled_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
@@ -1,248 +1,843 @@
-use crate::backend::{Auction, Funds};
+use crate::backend::{Auction, AuctionId, Funds, PlacedBid};
 use std::collections::HashMap;
 use std::error::Error;
-
-type MemoryStorageAuctionId = u64;
+use std::sync::Mutex;
 
 #[derive(Default)]
 pub struct AuctionsMemoryStorage {
-    auctions: HashMap<MemoryStorageAuctionId, Auction>,
-    next_id: MemoryStorageAuctionId,
+    auctions: Mutex<HashMap<AuctionId, Auction>>,
+    /// Concluded auctions which are being settled.
+    settling: Mutex<HashMap<AuctionId, Auction>>,
 }
 
-impl super::AuctionsBackend for AuctionsMemoryStorage {
-    type AuctionId = MemoryStorageAuctionId;
-
-    fn add_auction(&mut self, auction: Auction) -> Result<Self::AuctionId, Box<dyn Error>> {
-        let auction_id = self.next_id;
-        self.next_id += 1;
-        if self.auctions.contains_key(&auction_id) {
-            return Err("Lack of free auction ids!".into());
+impl AuctionsMemoryStorage {
+    fn place_bid(
+        &self,
+        auction_id: AuctionId,
+        bidder: &str,
+        max_amount: Funds,
+        proxy: bool,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        match self.auctions.lock().unwrap().get_mut(&auction_id) {
+            Some(auction) => {
+                auction.place_bid(bidder, max_amount, proxy, std::time::SystemTime::now())
+            }
+            None => Err("Auction does not exist".into()),
         }
-        self.auctions.insert(auction_id, auction);
+    }
+}
+
+#[tonic::async_trait]
+impl super::AuctionsBackend for AuctionsMemoryStorage {
+    async fn add_auction(
+        &self,
+        auction: Auction,
+    ) -> Result<AuctionId, Box<dyn Error + Send + Sync>> {
+        let auction_id = AuctionId::new();
+        self.auctions.lock().unwrap().insert(auction_id, auction);
         Ok(auction_id)
     }
 
-    fn bid_auction(
-        &mut self,
-        auction_id: Self::AuctionId,
+    async fn bid_auction(
+        &self,
+        auction_id: AuctionId,
         bidder: &str,
         amount: Funds,
-    ) -> Result<(), Box<dyn Error>> {
-        if self.auctions.contains_key(&auction_id) {
-            let auction = self.auctions.get_mut(&auction_id).unwrap();
-            if auction.seller == bidder {
-                return Err("Seller cannot bid on their own auction".into());
-            }
-            if auction.buyer == Some(bidder.to_owned()) {
-                return Err("Bidder is already the highest bidder".into());
-            }
-            if auction.current_price >= amount {
-                return Err("Bid amount is lower than the current price".into());
-            }
-            if auction.end_time < std::time::SystemTime::now() {
-                return Err("Auction is already concluded".into());
-            }
-            auction.buyer = Some(bidder.to_owned());
-            auction.current_price = amount;
-            Ok(())
-        } else {
-            Err("Auction does not exist".into())
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        self.place_bid(auction_id, bidder, amount, false)
+    }
+
+    async fn proxy_bid_auction(
+        &self,
+        auction_id: AuctionId,
+        bidder: &str,
+        max_amount: Funds,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        self.place_bid(auction_id, bidder, max_amount, true)
+    }
+
+    async fn get_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<Auction, Box<dyn Error + Send + Sync>> {
+        match self.auctions.lock().unwrap().get(&auction_id) {
+            Some(auction) => Ok(auction.clone()),
+            None => Err("Auction does not exist".into()),
+        }
+    }
+
+    async fn buy_now(
+        &self,
+        auction_id: AuctionId,
+        buyer: &str,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        let mut auctions = self.auctions.lock().unwrap();
+        let Some(auction) = auctions.get(&auction_id) else {
+            return Err("Auction does not exist".into());
+        };
+        if auction.seller == buyer {
+            return Err("Seller cannot buy their own item".into());
+        }
+        if auction.end_time < std::time::SystemTime::now() {
+            return Err("Auction is already concluded".into());
         }
+        let Some(buy_now_price) = auction.buy_now_price() else {
+            return Err("Buy now is not offered".into());
+        };
+        let mut auction = auctions.remove(&auction_id).unwrap();
+        let outbid = auction.held_bid();
+        auction.buyer = Some(buyer.to_owned());
+        auction.current_price = buy_now_price;
+        auction.max_bid = Some(buy_now_price);
+        auction.end_time = std::time::SystemTime::now();
+        self.settling
+            .lock()
+            .unwrap()
+            .insert(auction_id, auction.clone());
+        Ok(PlacedBid {
+            auction,
+            outbid,
+            extended: false,
+        })
     }
 
-    fn close_auction(&mut self, auction_id: Self::AuctionId) -> Result<Auction, Box<dyn Error>> {
-        if self.auctions.contains_key(&auction_id) {
-            {
-                let auction = self.auctions.get(&auction_id).unwrap();
-                if auction.end_time < std::time::SystemTime::now() {
-                    return Err("Auction is already concluded".into());
-                }
+    async fn close_auction(
+        &self,
+        auction_id: AuctionId,
+        seller: &str,
+    ) -> Result<Auction, Box<dyn Error + Send + Sync>> {
+        let mut auctions = self.auctions.lock().unwrap();
+        if let Some(auction) = auctions.get(&auction_id) {
+            if auction.seller != seller {
+                return Err("Only the seller can close the auction".into());
             }
-            Ok(self.auctions.remove(&auction_id).unwrap())
+            if auction.end_time < std::time::SystemTime::now() {
+                return Err("Auction is already concluded".into());
+            }
+            Ok(auctions.remove(&auction_id).unwrap())
         } else {
             Err("Auction does not exist".into())
         }
     }
 
-    fn list_ongoing_auctions(&self) -> Result<HashMap<Self::AuctionId, Auction>, Box<dyn Error>> {
+    async fn list_ongoing_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn Error + Send + Sync>> {
         Ok(self
             .auctions
+            .lock()
+            .unwrap()
             .iter()
             .filter(|(_, auction)| auction.end_time > std::time::SystemTime::now())
             .map(|(auction_id, auction)| (*auction_id, auction.clone()))
             .collect())
     }
 
-    fn pop_concluded_auctions(
-        &mut self,
-    ) -> Result<HashMap<Self::AuctionId, Auction>, Box<dyn Error>> {
-        let concluded = self
-            .auctions
+    async fn claim_concluded_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn Error + Send + Sync>> {
+        let mut auctions = self.auctions.lock().unwrap();
+        let concluded: HashMap<_, _> = auctions
             .iter()
             .filter(|(_, auction)| auction.end_time <= std::time::SystemTime::now())
             .map(|(auction_id, auction)| (*auction_id, auction.clone()))
             .collect();
-        for (auction_id, _) in &concluded {
-            self.auctions.remove(auction_id);
+        for auction_id in concluded.keys() {
+            auctions.remove(auction_id);
         }
+        self.settling.lock().unwrap().extend(concluded.clone());
         Ok(concluded)
     }
+
+    async fn list_settling_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn Error + Send + Sync>> {
+        Ok(self.settling.lock().unwrap().clone())
+    }
+
+    async fn remove_settled_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.settling.lock().unwrap().remove(&auction_id);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::backend::AuctionsBackend;
-    #[test]
-    fn test_add_auction() {
-        let mut storage = AuctionsMemoryStorage::default();
+    use crate::backend::{
+        AuctionType, AuctionsBackend, Bid, BidIncrement, PriceSchedule, PriceStep,
+    };
+    #[tokio::test]
+    async fn test_add_auction() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert_eq!(storage.auctions.get(&auction_id), Some(&auction));
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert_eq!(
+            storage.auctions.lock().unwrap().get(&auction_id),
+            Some(&auction)
+        );
     }
 
-    #[test]
-    fn test_bid_auction() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bid_auction() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert!(storage.bid_auction(auction_id, "bidder", 1).is_ok());
-        let stored_auction = storage.auctions.get(&auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "bidder", 1).await.is_ok());
+        let auctions = storage.auctions.lock().unwrap();
+        let stored_auction = auctions.get(&auction_id).unwrap();
         assert_eq!(stored_auction.buyer, Some("bidder".to_string()));
         assert_eq!(stored_auction.current_price, 1);
     }
 
-    #[test]
-    fn test_bid_auction_with_lower_price() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bids_increase_version() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let first = storage.bid_auction(auction_id, "bidder1", 1).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "bidder2", 1).await.is_err());
+        let second = storage.bid_auction(auction_id, "bidder2", 2).await.unwrap();
+        assert_eq!(first.auction.version(), 1);
+        assert_eq!(second.auction.version(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_with_lower_price() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 1, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert!(storage.bid_auction(auction_id, "bidder", 0).is_err());
-        let stored_auction = storage.auctions.get(&auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "bidder", 0).await.is_err());
+        let auctions = storage.auctions.lock().unwrap();
+        let stored_auction = auctions.get(&auction_id).unwrap();
         assert_eq!(stored_auction.buyer, None);
         assert_eq!(stored_auction.current_price, 0);
     }
 
-    #[test]
-    fn test_bid_auction_by_two_bidders() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bid_auction_below_starting_price() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 1000, std::time::Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let error = storage
+            .bid_auction(auction_id, "bidder", 1)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bid amount is lower than the minimum bid of 1000"
+        );
+        assert!(storage
+            .bid_auction(auction_id, "bidder", 1000)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_with_min_increment() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 100, std::time::Duration::from_secs(100), "seller")
+            .with_min_increment(BidIncrement::Amount(10));
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        assert!(storage
+            .bid_auction(auction_id, "bidder1", 100)
+            .await
+            .is_ok());
+        let error = storage
+            .bid_auction(auction_id, "bidder2", 109)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bid amount is lower than the minimum bid of 110"
+        );
+        assert!(storage
+            .bid_auction(auction_id, "bidder2", 110)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_min_bid_with_percent_increment() {
+        let auction = Auction::new("item", 100, std::time::Duration::from_secs(100), "seller")
+            .with_min_increment(BidIncrement::Percent(10));
+        let storage = AuctionsMemoryStorage::default();
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder1", 101)
+            .await
+            .unwrap();
+        // 10% of 101 is rounded up to 11
+        assert_eq!(placed.auction.min_bid(), 112);
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_by_two_bidders() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert!(storage.bid_auction(auction_id, "bidder1", 1).is_ok());
-        assert!(storage.bid_auction(auction_id, "bidder2", 2).is_ok());
-        let stored_auction = storage.auctions.get(&auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "bidder1", 1).await.is_ok());
+        assert!(storage.bid_auction(auction_id, "bidder2", 2).await.is_ok());
+        let auctions = storage.auctions.lock().unwrap();
+        let stored_auction = auctions.get(&auction_id).unwrap();
         assert_eq!(stored_auction.buyer, Some("bidder2".to_string()));
         assert_eq!(stored_auction.current_price, 2);
     }
 
-    #[test]
-    fn test_bid_auction_by_seller() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bid_auction_by_seller() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert!(storage.bid_auction(auction_id, "seller", 1).is_err());
-        let stored_auction = storage.auctions.get(&auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "seller", 1).await.is_err());
+        let auctions = storage.auctions.lock().unwrap();
+        let stored_auction = auctions.get(&auction_id).unwrap();
         assert_eq!(stored_auction.buyer, None);
         assert_eq!(stored_auction.current_price, 0);
     }
 
-    #[test]
-    fn test_bid_auction_that_does_not_exist() {
-        let mut storage = AuctionsMemoryStorage::default();
-        assert!(storage.bid_auction(0, "bidder", 1).is_err());
+    #[tokio::test]
+    async fn test_bid_auction_that_does_not_exist() {
+        let storage = AuctionsMemoryStorage::default();
+        assert!(storage
+            .bid_auction(AuctionId::new(), "bidder", 1)
+            .await
+            .is_err());
     }
 
-    #[test]
-    fn test_bid_auction_that_is_concluded() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bid_auction_that_is_concluded() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(0), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        assert!(storage.bid_auction(auction_id, "bidder", 1).is_err());
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        assert!(storage.bid_auction(auction_id, "bidder", 1).await.is_err());
     }
 
-    #[test]
-    fn test_bid_auction_by_the_same_bidder() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bid_auction_by_the_same_bidder() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert!(storage.bid_auction(auction_id, "bidder", 1).is_ok());
-        assert!(storage.bid_auction(auction_id, "bidder", 2).is_err());
-        let stored_auction = storage.auctions.get(&auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "bidder", 1).await.is_ok());
+        assert!(storage.bid_auction(auction_id, "bidder", 2).await.is_err());
+        let auctions = storage.auctions.lock().unwrap();
+        let stored_auction = auctions.get(&auction_id).unwrap();
         assert_eq!(stored_auction.buyer, Some("bidder".to_string()));
         assert_eq!(stored_auction.current_price, 1);
     }
 
-    #[test]
-    fn test_bid_auction_by_two_bidders_with_the_same_price() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_bid_auction_by_two_bidders_with_the_same_price() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        assert!(storage.bid_auction(auction_id, "bidder1", 1).is_ok());
-        assert!(storage.bid_auction(auction_id, "bidder2", 1).is_err());
-        let stored_auction = storage.auctions.get(&auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert!(storage.bid_auction(auction_id, "bidder1", 1).await.is_ok());
+        assert!(storage.bid_auction(auction_id, "bidder2", 1).await.is_err());
+        let auctions = storage.auctions.lock().unwrap();
+        let stored_auction = auctions.get(&auction_id).unwrap();
         assert_eq!(stored_auction.buyer, Some("bidder1".to_string()));
         assert_eq!(stored_auction.current_price, 1);
     }
 
-    #[test]
-    fn test_close_auction() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_close_auction() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        let closed_auction = storage.close_auction(auction_id).unwrap();
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        let closed_auction = storage.close_auction(auction_id, "seller").await.unwrap();
         assert_eq!(closed_auction.seller, "seller");
         assert_eq!(closed_auction.buyer, None);
         assert_eq!(closed_auction.current_price, 0);
         assert_eq!(closed_auction.item, "item");
-        assert!(storage.auctions.get(&auction_id).is_none());
+        assert!(!storage.auctions.lock().unwrap().contains_key(&auction_id));
     }
 
-    #[test]
-    fn test_close_auction_that_does_not_exist() {
-        let mut storage = AuctionsMemoryStorage::default();
-        assert!(storage.close_auction(0).is_err());
+    #[tokio::test]
+    async fn test_close_auction_that_does_not_exist() {
+        let storage = AuctionsMemoryStorage::default();
+        assert!(storage
+            .close_auction(AuctionId::new(), "seller")
+            .await
+            .is_err());
     }
 
-    #[test]
-    fn test_close_auction_that_is_already_concluded() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_close_auction_that_is_already_concluded() {
+        let storage = AuctionsMemoryStorage::default();
         let auction = Auction::new("item", 0, std::time::Duration::from_secs(0), "seller");
-        let auction_id = storage.add_auction(auction.clone()).unwrap();
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        assert!(storage.close_auction(auction_id).is_err());
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        assert!(storage.close_auction(auction_id, "seller").await.is_err());
     }
 
-    #[test]
-    fn test_list_ongoing_auctions() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_list_ongoing_auctions() {
+        let storage = AuctionsMemoryStorage::default();
         let auction1 = Auction::new("item1", 0, std::time::Duration::from_secs(100), "seller1");
         let auction2 = Auction::new("item2", 0, std::time::Duration::from_secs(0), "seller2");
         let auction3 = Auction::new("item3", 0, std::time::Duration::from_secs(100), "seller3");
-        let auction_id1 = storage.add_auction(auction1.clone()).unwrap();
-        let auction_id2 = storage.add_auction(auction2.clone()).unwrap();
-        let auction_id3 = storage.add_auction(auction3.clone()).unwrap();
-        let ongoing_auctions = storage.list_ongoing_auctions().unwrap();
+        let auction_id1 = storage.add_auction(auction1.clone()).await.unwrap();
+        let auction_id2 = storage.add_auction(auction2.clone()).await.unwrap();
+        let auction_id3 = storage.add_auction(auction3.clone()).await.unwrap();
+        let ongoing_auctions = storage.list_ongoing_auctions().await.unwrap();
         assert_eq!(ongoing_auctions.len(), 2);
+        assert!(!ongoing_auctions.contains_key(&auction_id2));
         assert_eq!(ongoing_auctions[&auction_id1], auction1);
         assert_eq!(ongoing_auctions[&auction_id3], auction3);
     }
 
-    #[test]
-    fn test_pop_concluded_auctions() {
-        let mut storage = AuctionsMemoryStorage::default();
+    #[tokio::test]
+    async fn test_claim_concluded_auctions() {
+        let storage = AuctionsMemoryStorage::default();
         let auction1 = Auction::new("item1", 0, std::time::Duration::from_secs(100), "seller1");
         let auction2 = Auction::new("item2", 0, std::time::Duration::from_secs(0), "seller2");
         let auction3 = Auction::new("item3", 0, std::time::Duration::from_secs(100), "seller3");
-        let auction_id1 = storage.add_auction(auction1.clone()).unwrap();
-        let auction_id2 = storage.add_auction(auction2.clone()).unwrap();
-        let auction_id3 = storage.add_auction(auction3.clone()).unwrap();
-        let concluded_auctions = storage.pop_concluded_auctions().unwrap();
+        let auction_id1 = storage.add_auction(auction1.clone()).await.unwrap();
+        let auction_id2 = storage.add_auction(auction2.clone()).await.unwrap();
+        let auction_id3 = storage.add_auction(auction3.clone()).await.unwrap();
+        let concluded_auctions = storage.claim_concluded_auctions().await.unwrap();
         assert_eq!(concluded_auctions.len(), 1);
         assert_eq!(concluded_auctions[&auction_id2], auction2);
-        assert!(storage.auctions.get(&auction_id1).is_some());
-        assert!(storage.auctions.get(&auction_id3).is_some());
-        assert_eq!(storage.auctions.len(), 2);
+        assert!(storage.auctions.lock().unwrap().contains_key(&auction_id1));
+        assert!(storage.auctions.lock().unwrap().contains_key(&auction_id3));
+        assert_eq!(storage.auctions.lock().unwrap().len(), 2);
+        assert!(storage.claim_concluded_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_claimed_auctions_are_settling_until_removed() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(0), "seller");
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        storage.claim_concluded_auctions().await.unwrap();
+        let settling = storage.list_settling_auctions().await.unwrap();
+        assert_eq!(settling.len(), 1);
+        assert_eq!(settling[&auction_id], auction);
+        storage.remove_settled_auction(auction_id).await.unwrap();
+        assert!(storage.list_settling_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_returns_outbid_bid() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        assert_eq!(
+            storage
+                .bid_auction(auction_id, "bidder1", 1)
+                .await
+                .unwrap()
+                .outbid,
+            None
+        );
+        assert_eq!(
+            storage
+                .bid_auction(auction_id, "bidder2", 2)
+                .await
+                .unwrap()
+                .outbid,
+            Some(Bid {
+                bidder: "bidder1".to_string(),
+                amount: 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_within_soft_close_window() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller")
+            .with_soft_close(
+                std::time::Duration::from_secs(200),
+                std::time::Duration::from_secs(60),
+                Some(std::time::Duration::from_secs(90)),
+            );
+        let end_time = auction.end_time();
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage.bid_auction(auction_id, "bidder1", 1).await.unwrap();
+        assert!(placed.extended);
+        assert_eq!(
+            placed.auction.end_time(),
+            end_time + std::time::Duration::from_secs(60)
+        );
+        // the second extension is capped
+        let placed = storage.bid_auction(auction_id, "bidder2", 2).await.unwrap();
+        assert!(placed.extended);
+        assert_eq!(
+            placed.auction.end_time(),
+            end_time + std::time::Duration::from_secs(90)
+        );
+        let placed = storage.bid_auction(auction_id, "bidder1", 3).await.unwrap();
+        assert!(!placed.extended);
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_with_unrepresentable_soft_close() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
+        let end_time = auction.end_time();
+        let auction = auction.with_soft_close(
+            std::time::Duration::MAX,
+            std::time::Duration::MAX,
+            Some(std::time::Duration::MAX),
+        );
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage.bid_auction(auction_id, "bidder", 1).await.unwrap();
+        assert!(!placed.extended);
+        assert_eq!(placed.auction.end_time(), end_time);
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller")
+            .with_soft_close(
+                std::time::Duration::from_secs(u64::MAX),
+                std::time::Duration::from_secs(u64::MAX),
+                Some(std::time::Duration::from_secs(60)),
+            );
+        let end_time = auction.end_time();
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage.bid_auction(auction_id, "bidder", 1).await.unwrap();
+        assert!(placed.extended);
+        assert_eq!(
+            placed.auction.end_time(),
+            end_time + std::time::Duration::from_secs(60)
+        );
+        assert!(storage.bid_auction(auction_id, "other", 2).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bid_auction_outside_soft_close_window() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller")
+            .with_soft_close(
+                std::time::Duration::from_secs(10),
+                std::time::Duration::from_secs(60),
+                None,
+            );
+        let end_time = auction.end_time();
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage.bid_auction(auction_id, "bidder", 1).await.unwrap();
+        assert!(!placed.extended);
+        assert_eq!(placed.auction.end_time(), end_time);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_bid_opens_at_starting_price() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 10, std::time::Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder", 50)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.current_price(), 10);
+        assert_eq!(
+            placed.auction.held_bid(),
+            Some(Bid {
+                bidder: "bidder".to_string(),
+                amount: 50
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proxy_bid_outbids_lower_bid_at_once() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 10, std::time::Duration::from_secs(100), "seller")
+            .with_min_increment(BidIncrement::Amount(5));
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        storage
+            .proxy_bid_auction(auction_id, "bidder1", 50)
+            .await
+            .unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder2", 30)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.buyer(), Some("bidder1"));
+        assert_eq!(placed.auction.current_price(), 35);
+        assert_eq!(
+            placed.outbid,
+            Some(Bid {
+                bidder: "bidder2".to_string(),
+                amount: 30
+            })
+        );
+        // an equal maximum does not beat the earlier one
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder2", 50)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.buyer(), Some("bidder1"));
+        assert_eq!(placed.auction.current_price(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_bid_meets_reserve_price() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 10, std::time::Duration::from_secs(100), "seller")
+            .with_reserve_price(100);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder1", 150)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.current_price(), 100);
+        assert!(placed.auction.reserve_met());
+        // the maximum keeps outbidding lower bids in minimum increments
+        let placed = storage
+            .bid_auction(auction_id, "bidder2", 120)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.buyer(), Some("bidder1"));
+        assert_eq!(placed.auction.current_price(), 121);
+        // a maximum below the reserve is not revealed
+        let auction = Auction::new("item", 10, std::time::Duration::from_secs(100), "seller")
+            .with_reserve_price(100);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder1", 80)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.current_price(), 10);
+        assert!(!placed.auction.reserve_met());
+    }
+
+    #[tokio::test]
+    async fn test_competing_proxy_bids() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 10, std::time::Duration::from_secs(100), "seller")
+            .with_min_increment(BidIncrement::Amount(5));
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        storage
+            .proxy_bid_auction(auction_id, "bidder1", 50)
+            .await
+            .unwrap();
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder2", 80)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.buyer(), Some("bidder2"));
+        assert_eq!(placed.auction.current_price(), 55);
+        assert_eq!(
+            placed.outbid,
+            Some(Bid {
+                bidder: "bidder1".to_string(),
+                amount: 50
+            })
+        );
+        // the price never exceeds the winning maximum
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder3", 78)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.buyer(), Some("bidder2"));
+        assert_eq!(placed.auction.current_price(), 80);
+    }
+
+    #[tokio::test]
+    async fn test_buy_now() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller")
+            .with_buy_now_price(50);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        storage.bid_auction(auction_id, "bidder", 10).await.unwrap();
+        assert!(storage.buy_now(auction_id, "seller").await.is_err());
+        let bought = storage.buy_now(auction_id, "buyer").await.unwrap();
+        assert_eq!(bought.auction.buyer(), Some("buyer"));
+        assert_eq!(bought.auction.current_price(), 50);
+        assert_eq!(
+            bought.outbid,
+            Some(Bid {
+                bidder: "bidder".to_string(),
+                amount: 10
+            })
+        );
+        assert!(storage.auctions.lock().unwrap().is_empty());
+        let settling = storage.list_settling_auctions().await.unwrap();
+        assert_eq!(settling[&auction_id].buyer(), Some("buyer"));
+    }
+
+    #[tokio::test]
+    async fn test_buy_now_after_bid_exceeds_it() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller")
+            .with_buy_now_price(50);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage.bid_auction(auction_id, "bidder", 51).await.unwrap();
+        assert_eq!(placed.auction.buy_now_price(), None);
+        let error = storage.buy_now(auction_id, "buyer").await.unwrap_err();
+        assert_eq!(error.to_string(), "Buy now is not offered");
+        assert!(storage.auctions.lock().unwrap().contains_key(&auction_id));
+    }
+
+    #[tokio::test]
+    async fn test_close_auction_by_other_user() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 0, std::time::Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        assert!(storage.close_auction(auction_id, "other").await.is_err());
+        assert!(storage.auctions.lock().unwrap().contains_key(&auction_id));
+    }
+
+    fn dutch_auction(step: Option<PriceStep>) -> Auction {
+        Auction::new("item", 100, std::time::Duration::from_secs(100), "seller").with_type(
+            AuctionType::Dutch(PriceSchedule {
+                floor_price: 40,
+                step,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_dutch_price_drops_linearly_to_floor() {
+        let auction = dutch_auction(None);
+        let start_time = auction.start_time();
+        assert_eq!(auction.price_at(start_time), 100);
+        assert_eq!(
+            auction.price_at(start_time + std::time::Duration::from_secs(50)),
+            70
+        );
+        assert_eq!(auction.price_at(auction.end_time()), 40);
+        assert_eq!(
+            auction.price_at(auction.end_time() + std::time::Duration::from_secs(50)),
+            40
+        );
+    }
+
+    #[test]
+    fn test_dutch_price_drops_in_steps_to_floor() {
+        let auction = dutch_auction(Some(PriceStep {
+            amount: 15,
+            interval: std::time::Duration::from_secs(10),
+        }));
+        let start_time = auction.start_time();
+        assert_eq!(
+            auction.price_at(start_time + std::time::Duration::from_secs(9)),
+            100
+        );
+        assert_eq!(
+            auction.price_at(start_time + std::time::Duration::from_secs(25)),
+            70
+        );
+        assert_eq!(
+            auction.price_at(start_time + std::time::Duration::from_secs(90)),
+            40
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dutch_bid_wins_at_current_price() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction_id = storage.add_auction(dutch_auction(None)).await.unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder", 150)
+            .await
+            .unwrap();
+        assert!(placed.auction.current_price() <= 100);
+        assert_eq!(
+            placed.auction.held_bid(),
+            Some(Bid {
+                bidder: "bidder".to_string(),
+                amount: 150
+            })
+        );
+        assert!(placed.auction.end_time() <= std::time::SystemTime::now());
+        let error = storage
+            .bid_auction(auction_id, "other", 150)
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Auction is already concluded");
+    }
+
+    #[tokio::test]
+    async fn test_dutch_bid_below_current_price() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 100, std::time::Duration::from_secs(1000), "seller")
+            .with_type(AuctionType::Dutch(PriceSchedule {
+                floor_price: 40,
+                step: None,
+            }));
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let error = storage
+            .bid_auction(auction_id, "bidder", 50)
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Bid amount is lower than the current price of"));
+        assert_eq!(storage.auctions.lock().unwrap()[&auction_id].buyer, None);
+    }
+
+    #[tokio::test]
+    async fn test_dutch_auction_rejects_proxy_bids() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction_id = storage.add_auction(dutch_auction(None)).await.unwrap();
+        let error = storage
+            .proxy_bid_auction(auction_id, "bidder", 150)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Proxy bids are not accepted in Dutch auctions"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sealed_bids_are_hidden_and_revised() {
+        let storage = AuctionsMemoryStorage::default();
+        let auction = Auction::new("item", 10, std::time::Duration::from_secs(100), "seller")
+            .with_type(AuctionType::SealedFirstPrice);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder1", 30)
+            .await
+            .unwrap();
+        assert_eq!(placed.outbid, None);
+        storage
+            .bid_auction(auction_id, "bidder2", 20)
+            .await
+            .unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder1", 15)
+            .await
+            .unwrap();
+        assert_eq!(
+            placed.outbid,
+            Some(Bid {
+                bidder: "bidder1".to_string(),
+                amount: 30
+            })
+        );
+        assert_eq!(placed.auction.buyer(), None);
+        assert_eq!(placed.auction.min_bid(), 10);
+        assert_eq!(placed.auction.bid_count(), 2);
+        let error = storage
+            .bid_auction(auction_id, "bidder2", 9)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bid amount is lower than the minimum bid of 10"
+        );
+        let error = storage
+            .proxy_bid_auction(auction_id, "bidder2", 50)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Proxy bids are not accepted in sealed-bid auctions"
+        );
+        let mut auction = storage.get_auction(auction_id).await.unwrap();
+        assert_eq!(
+            auction.unseal(),
+            vec![Bid {
+                bidder: "bidder1".to_string(),
+                amount: 15
+            }]
+        );
+        assert_eq!(auction.buyer(), Some("bidder2"));
+        assert_eq!(auction.current_price(), 20);
     }
 }
@@ -0,0 +1,763 @@
+use crate::backend::{
+    Auction, AuctionId, AuctionType, Bid, BidIncrement, Funds, PlacedBid, PriceSchedule, PriceStep,
+    SoftClose,
+};
+use mongodb::bson::{doc, to_bson, Bson, DateTime};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+
+const COLLECTION: &str = "Auctions";
+
+/// Document of the `Auctions` collection.
+#[derive(Serialize, Deserialize)]
+struct AuctionDocument {
+    #[serde(rename = "_id")]
+    id: AuctionId,
+    item: String,
+    starting_price: i64,
+    current_price: i64,
+    #[serde(default)]
+    min_increment: BidIncrement,
+    #[serde(default)]
+    reserve_price: Option<i64>,
+    #[serde(default)]
+    buy_now_price: Option<i64>,
+    #[serde(default)]
+    soft_close: Option<SoftCloseDocument>,
+    #[serde(default)]
+    auction_type: AuctionTypeDocument,
+    start_time: DateTime,
+    end_time: DateTime,
+    bidder: Option<String>,
+    #[serde(default)]
+    max_bid: Option<i64>,
+    #[serde(default)]
+    sealed_bids: Vec<SealedBidDocument>,
+    #[serde(default)]
+    version: i64,
+    #[serde(default)]
+    bidders: Vec<String>,
+    owner_id: String,
+    /// Set when the concluded auction is claimed for settlement, it is removed once settled.
+    #[serde(default)]
+    settling: bool,
+}
+
+/// A sealed bid of an auction.
+#[derive(Serialize, Deserialize)]
+struct SealedBidDocument {
+    bidder: String,
+    amount: i64,
+}
+
+impl SealedBidDocument {
+    fn new(bid: &Bid) -> Self {
+        Self {
+            bidder: bid.bidder.clone(),
+            amount: bid.amount.into(),
+        }
+    }
+
+    fn into_bid(self) -> Result<Bid, Box<dyn Error + Send + Sync>> {
+        Ok(Bid {
+            bidder: self.bidder,
+            amount: Funds::try_from(self.amount)?,
+        })
+    }
+}
+
+/// Returns the sealed bids as they are stored.
+fn sealed_bids_to_bson(bids: &[Bid]) -> Result<Bson, Box<dyn Error + Send + Sync>> {
+    let bids: Vec<_> = bids.iter().map(SealedBidDocument::new).collect();
+    Ok(to_bson(&bids)?)
+}
+
+/// Soft close of an auction, durations are in milliseconds.
+#[derive(Serialize, Deserialize)]
+struct SoftCloseDocument {
+    window: i64,
+    extension: i64,
+    max_end_time: Option<DateTime>,
+}
+
+impl SoftCloseDocument {
+    fn new(soft_close: SoftClose) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            window: i64::try_from(soft_close.window.as_millis())?,
+            extension: i64::try_from(soft_close.extension.as_millis())?,
+            max_end_time: soft_close.max_end_time.map(DateTime::from),
+        })
+    }
+
+    fn into_soft_close(self) -> Result<SoftClose, Box<dyn Error + Send + Sync>> {
+        Ok(SoftClose {
+            window: Duration::from_millis(u64::try_from(self.window)?),
+            extension: Duration::from_millis(u64::try_from(self.extension)?),
+            max_end_time: self.max_end_time.map(SystemTime::from),
+        })
+    }
+}
+
+/// Type of an auction, the step interval is in milliseconds.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AuctionTypeDocument {
+    #[default]
+    English,
+    Dutch {
+        floor_price: i64,
+        step_amount: Option<i64>,
+        step_interval: Option<i64>,
+    },
+    SealedFirstPrice,
+    SealedSecondPrice,
+}
+
+impl AuctionTypeDocument {
+    fn new(auction_type: AuctionType) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(match auction_type {
+            AuctionType::English => Self::English,
+            AuctionType::SealedFirstPrice => Self::SealedFirstPrice,
+            AuctionType::SealedSecondPrice => Self::SealedSecondPrice,
+            AuctionType::Dutch(schedule) => Self::Dutch {
+                floor_price: schedule.floor_price.into(),
+                step_amount: schedule.step.map(|step| step.amount.into()),
+                step_interval: schedule
+                    .step
+                    .map(|step| i64::try_from(step.interval.as_millis()))
+                    .transpose()?,
+            },
+        })
+    }
+
+    fn into_auction_type(self) -> Result<AuctionType, Box<dyn Error + Send + Sync>> {
+        Ok(match self {
+            Self::English => AuctionType::English,
+            Self::SealedFirstPrice => AuctionType::SealedFirstPrice,
+            Self::SealedSecondPrice => AuctionType::SealedSecondPrice,
+            Self::Dutch {
+                floor_price,
+                step_amount,
+                step_interval,
+            } => {
+                let step = match (step_amount, step_interval) {
+                    (Some(amount), Some(interval)) => Some(PriceStep {
+                        amount: Funds::try_from(amount)?,
+                        interval: Duration::from_millis(u64::try_from(interval)?),
+                    }),
+                    _ => None,
+                };
+                AuctionType::Dutch(PriceSchedule {
+                    floor_price: Funds::try_from(floor_price)?,
+                    step,
+                })
+            }
+        })
+    }
+}
+
+impl AuctionDocument {
+    fn new(id: AuctionId, auction: Auction) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            id,
+            item: auction.item,
+            starting_price: auction.starting_price.into(),
+            current_price: auction.current_price.into(),
+            min_increment: auction.min_increment,
+            reserve_price: auction.reserve_price.map(i64::from),
+            buy_now_price: auction.buy_now_price.map(i64::from),
+            soft_close: auction.soft_close.map(SoftCloseDocument::new).transpose()?,
+            auction_type: AuctionTypeDocument::new(auction.auction_type)?,
+            start_time: auction.start_time.into(),
+            end_time: auction.end_time.into(),
+            bidder: auction.buyer,
+            max_bid: auction.max_bid.map(i64::from),
+            sealed_bids: auction
+                .sealed_bids
+                .iter()
+                .map(SealedBidDocument::new)
+                .collect(),
+            version: i64::try_from(auction.version)?,
+            bidders: auction.bidders,
+            owner_id: auction.seller,
+            settling: false,
+        })
+    }
+
+    fn into_auction(self) -> Result<(AuctionId, Auction), Box<dyn Error + Send + Sync>> {
+        let auction = Auction {
+            item: self.item,
+            starting_price: Funds::try_from(self.starting_price)?,
+            current_price: Funds::try_from(self.current_price)?,
+            min_increment: self.min_increment,
+            reserve_price: self.reserve_price.map(Funds::try_from).transpose()?,
+            buy_now_price: self.buy_now_price.map(Funds::try_from).transpose()?,
+            soft_close: self
+                .soft_close
+                .map(SoftCloseDocument::into_soft_close)
+                .transpose()?,
+            auction_type: self.auction_type.into_auction_type()?,
+            start_time: self.start_time.into(),
+            end_time: self.end_time.into(),
+            seller: self.owner_id,
+            buyer: self.bidder,
+            max_bid: self.max_bid.map(Funds::try_from).transpose()?,
+            sealed_bids: self
+                .sealed_bids
+                .into_iter()
+                .map(SealedBidDocument::into_bid)
+                .collect::<Result<_, _>>()?,
+            version: u64::try_from(self.version)?,
+            bidders: self.bidders,
+        };
+        Ok((self.id, auction))
+    }
+}
+
+/// Stores auctions in MongoDB.
+///
+/// Bids are conditional updates checked against the highest bid they have been validated with,
+/// so concurrent bids cannot overwrite a higher one. Auctions which are being settled are kept
+/// apart from the others, so a settlement interrupted by a crash can be resumed.
+pub struct AuctionsMongoStorage {
+    auctions: Collection<AuctionDocument>,
+}
+
+fn now() -> DateTime {
+    SystemTime::now().into()
+}
+
+impl AuctionsMongoStorage {
+    /// Creates the storage and makes sure the index on end times exists.
+    pub async fn new(database: &Database) -> Result<Self, mongodb::error::Error> {
+        let auctions = database.collection(COLLECTION);
+        let index = IndexModel::builder().keys(doc! { "end_time": 1 }).build();
+        auctions.create_index(index, None).await?;
+        Ok(Self { auctions })
+    }
+
+    async fn find_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<AuctionDocument, Box<dyn Error + Send + Sync>> {
+        match self
+            .auctions
+            .find_one(
+                doc! { "_id": auction_id.to_string(), "settling": { "$ne": true } },
+                None,
+            )
+            .await?
+        {
+            Some(auction) => Ok(auction),
+            None => Err("Auction does not exist".into()),
+        }
+    }
+
+    async fn place_bid(
+        &self,
+        auction_id: AuctionId,
+        bidder: &str,
+        max_amount: Funds,
+        proxy: bool,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        loop {
+            let (_, auction) = self.find_auction(auction_id).await?.into_auction()?;
+            let placed = auction
+                .clone()
+                .place_bid(bidder, max_amount, proxy, SystemTime::now())?;
+            // the bid is resolved against the highest bid which has been read, so it is applied
+            // only if the highest bid has not changed since then, otherwise it is placed again
+            let mut filter = doc! {
+                "_id": auction_id.to_string(),
+                "bidder": auction.buyer.as_deref(),
+                "current_price": i64::from(auction.current_price),
+                "max_bid": auction.max_bid.map(i64::from),
+                "end_time": { "$gte": now() },
+                "settling": { "$ne": true },
+                // auctions stored before versions were added have none
+                "$expr": { "$eq": [{ "$ifNull": ["$version", 0] }, i64::try_from(auction.version)?] },
+            };
+            let mut update = doc! {
+                "bidder": placed.auction.buyer.as_deref(),
+                "current_price": i64::from(placed.auction.current_price),
+                "max_bid": placed.auction.max_bid.map(i64::from),
+                "end_time": DateTime::from(placed.auction.end_time),
+                "version": i64::try_from(placed.auction.version)?,
+                "bidders": &placed.auction.bidders,
+            };
+            // sealed bids are stored from the start, so the ones which have been read are compared
+            if auction.is_sealed() {
+                filter.insert("sealed_bids", sealed_bids_to_bson(&auction.sealed_bids)?);
+                update.insert(
+                    "sealed_bids",
+                    sealed_bids_to_bson(&placed.auction.sealed_bids)?,
+                );
+            }
+            let updated = self
+                .auctions
+                .update_one(filter, doc! { "$set": update }, None)
+                .await?;
+            if updated.matched_count > 0 {
+                return Ok(placed);
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl super::AuctionsBackend for AuctionsMongoStorage {
+    async fn add_auction(
+        &self,
+        auction: Auction,
+    ) -> Result<AuctionId, Box<dyn Error + Send + Sync>> {
+        let auction_id = AuctionId::new();
+        self.auctions
+            .insert_one(AuctionDocument::new(auction_id, auction)?, None)
+            .await?;
+        Ok(auction_id)
+    }
+
+    async fn bid_auction(
+        &self,
+        auction_id: AuctionId,
+        bidder: &str,
+        amount: Funds,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        self.place_bid(auction_id, bidder, amount, false).await
+    }
+
+    async fn proxy_bid_auction(
+        &self,
+        auction_id: AuctionId,
+        bidder: &str,
+        max_amount: Funds,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        self.place_bid(auction_id, bidder, max_amount, true).await
+    }
+
+    async fn get_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<Auction, Box<dyn Error + Send + Sync>> {
+        Ok(self.find_auction(auction_id).await?.into_auction()?.1)
+    }
+
+    async fn buy_now(
+        &self,
+        auction_id: AuctionId,
+        buyer: &str,
+    ) -> Result<PlacedBid, Box<dyn Error + Send + Sync>> {
+        let end_time = now();
+        // the auction is ended and claimed for settlement in one update, the previous state
+        // is returned to tell the outbid bid
+        let bought = self
+            .auctions
+            .find_one_and_update(
+                doc! {
+                    "_id": auction_id.to_string(),
+                    "owner_id": { "$ne": buyer },
+                    "end_time": { "$gte": end_time },
+                    "settling": { "$ne": true },
+                    "buy_now_price": { "$ne": null },
+                    "$expr": {
+                        "$or": [
+                            { "$eq": [{ "$ifNull": ["$bidder", null] }, null] },
+                            { "$lte": ["$current_price", "$buy_now_price"] },
+                        ]
+                    },
+                },
+                vec![doc! {
+                    "$set": {
+                        "bidder": { "$literal": buyer },
+                        "current_price": "$buy_now_price",
+                        "max_bid": "$buy_now_price",
+                        "end_time": end_time,
+                        "settling": true,
+                    }
+                }],
+                None,
+            )
+            .await?;
+        let Some(bought) = bought else {
+            // the same checks as in the filter, to tell why the purchase has been rejected
+            let (_, auction) = self.find_auction(auction_id).await?.into_auction()?;
+            if auction.seller == buyer {
+                return Err("Seller cannot buy their own item".into());
+            }
+            if auction.end_time < SystemTime::now() {
+                return Err("Auction is already concluded".into());
+            }
+            return Err("Buy now is not offered".into());
+        };
+        let (_, mut auction) = bought.into_auction()?;
+        let Some(buy_now_price) = auction.buy_now_price() else {
+            return Err("Buy now is not offered".into());
+        };
+        let outbid = auction.held_bid();
+        auction.buyer = Some(buyer.to_owned());
+        auction.current_price = buy_now_price;
+        auction.max_bid = Some(buy_now_price);
+        auction.end_time = end_time.into();
+        Ok(PlacedBid {
+            auction,
+            outbid,
+            extended: false,
+        })
+    }
+
+    async fn close_auction(
+        &self,
+        auction_id: AuctionId,
+        seller: &str,
+    ) -> Result<Auction, Box<dyn Error + Send + Sync>> {
+        let deleted = self
+            .auctions
+            .find_one_and_delete(
+                doc! {
+                    "_id": auction_id.to_string(),
+                    "owner_id": seller,
+                    "end_time": { "$gte": now() },
+                    "settling": { "$ne": true },
+                },
+                None,
+            )
+            .await?;
+        match deleted {
+            Some(auction) => Ok(auction.into_auction()?.1),
+            None => {
+                if self.find_auction(auction_id).await?.owner_id != seller {
+                    return Err("Only the seller can close the auction".into());
+                }
+                Err("Auction is already concluded".into())
+            }
+        }
+    }
+
+    async fn list_ongoing_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn Error + Send + Sync>> {
+        let mut cursor = self
+            .auctions
+            .find(
+                doc! { "end_time": { "$gt": now() }, "settling": { "$ne": true } },
+                None,
+            )
+            .await?;
+        let mut auctions = HashMap::new();
+        while cursor.advance().await? {
+            let (auction_id, auction) = cursor.deserialize_current()?.into_auction()?;
+            auctions.insert(auction_id, auction);
+        }
+        Ok(auctions)
+    }
+
+    async fn claim_concluded_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn Error + Send + Sync>> {
+        // auctions are claimed one by one, so each of them is claimed by exactly one caller
+        let mut concluded = HashMap::new();
+        while let Some(auction) = self
+            .auctions
+            .find_one_and_update(
+                doc! { "end_time": { "$lte": now() }, "settling": { "$ne": true } },
+                doc! { "$set": { "settling": true } },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await?
+        {
+            let (auction_id, auction) = auction.into_auction()?;
+            concluded.insert(auction_id, auction);
+        }
+        Ok(concluded)
+    }
+
+    async fn list_settling_auctions(
+        &self,
+    ) -> Result<HashMap<AuctionId, Auction>, Box<dyn Error + Send + Sync>> {
+        let mut cursor = self.auctions.find(doc! { "settling": true }, None).await?;
+        let mut auctions = HashMap::new();
+        while cursor.advance().await? {
+            let (auction_id, auction) = cursor.deserialize_current()?.into_auction()?;
+            auctions.insert(auction_id, auction);
+        }
+        Ok(auctions)
+    }
+
+    async fn remove_settled_auction(
+        &self,
+        auction_id: AuctionId,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.auctions
+            .delete_one(
+                doc! { "_id": auction_id.to_string(), "settling": true },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// The tests require a running MongoDB, run them with `cargo test -- --ignored`.
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::mongo::test_database;
+    use crate::backend::{AuctionsBackend, Bid, PriceSchedule, PriceStep};
+
+    async fn create_storage(name: &str) -> AuctionsMongoStorage {
+        AuctionsMongoStorage::new(&test_database(name).await)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_add_auction_and_bid() {
+        let storage = create_storage("AuctionsTestBid").await;
+        let auction = Auction::new("item", 0, Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        assert_eq!(
+            storage
+                .bid_auction(auction_id, "bidder1", 1)
+                .await
+                .unwrap()
+                .outbid,
+            None
+        );
+        assert!(storage.bid_auction(auction_id, "bidder2", 1).await.is_err());
+        assert!(storage.bid_auction(auction_id, "bidder1", 2).await.is_err());
+        assert!(storage.bid_auction(auction_id, "seller", 2).await.is_err());
+        let auctions = storage.list_ongoing_auctions().await.unwrap();
+        assert_eq!(auctions[&auction_id].buyer(), Some("bidder1"));
+        assert_eq!(auctions[&auction_id].current_price(), 1);
+        assert_eq!(
+            storage
+                .bid_auction(auction_id, "bidder2", 2)
+                .await
+                .unwrap()
+                .outbid,
+            Some(Bid {
+                bidder: "bidder1".to_string(),
+                amount: 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_bid_auction_with_min_increment() {
+        let storage = create_storage("AuctionsTestBidMinIncrement").await;
+        let auction = Auction::new("item", 100, Duration::from_secs(100), "seller")
+            .with_min_increment(BidIncrement::Percent(10));
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        assert!(storage
+            .bid_auction(auction_id, "bidder1", 99)
+            .await
+            .is_err());
+        storage
+            .bid_auction(auction_id, "bidder1", 100)
+            .await
+            .unwrap();
+        let error = storage
+            .bid_auction(auction_id, "bidder2", 109)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bid amount is lower than the minimum bid of 110"
+        );
+        storage
+            .bid_auction(auction_id, "bidder2", 110)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_bid_auction_within_soft_close_window() {
+        let storage = create_storage("AuctionsTestSoftClose").await;
+        let auction = Auction::new("item", 0, Duration::from_secs(100), "seller").with_soft_close(
+            Duration::from_secs(150),
+            Duration::from_secs(60),
+            None,
+        );
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        let end_time = storage.get_auction(auction_id).await.unwrap().end_time();
+        let placed = storage.bid_auction(auction_id, "bidder", 1).await.unwrap();
+        assert!(placed.extended);
+        assert_eq!(
+            placed.auction.end_time(),
+            end_time + Duration::from_secs(60)
+        );
+        assert_eq!(
+            storage.get_auction(auction_id).await.unwrap().end_time(),
+            end_time + Duration::from_secs(60)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_competing_proxy_bids() {
+        let storage = create_storage("AuctionsTestProxyBids").await;
+        let auction = Auction::new("item", 10, Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        storage
+            .proxy_bid_auction(auction_id, "bidder1", 50)
+            .await
+            .unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder2", 30)
+            .await
+            .unwrap();
+        assert_eq!(placed.outbid.unwrap().bidder, "bidder2");
+        let placed = storage
+            .proxy_bid_auction(auction_id, "bidder2", 80)
+            .await
+            .unwrap();
+        assert_eq!(placed.outbid.unwrap().amount, 50);
+        let auction = storage.get_auction(auction_id).await.unwrap();
+        assert_eq!(auction.buyer(), Some("bidder2"));
+        assert_eq!(auction.current_price(), 51);
+        assert_eq!(auction.held_bid().unwrap().amount, 80);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_buy_now() {
+        let storage = create_storage("AuctionsTestBuyNow").await;
+        let auction =
+            Auction::new("item", 0, Duration::from_secs(100), "seller").with_buy_now_price(50);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        storage.bid_auction(auction_id, "bidder", 10).await.unwrap();
+        assert!(storage.buy_now(auction_id, "seller").await.is_err());
+        let bought = storage.buy_now(auction_id, "buyer").await.unwrap();
+        assert_eq!(bought.auction.buyer(), Some("buyer"));
+        assert_eq!(bought.auction.current_price(), 50);
+        assert_eq!(bought.outbid.unwrap().bidder, "bidder");
+        assert!(storage.get_auction(auction_id).await.is_err());
+        let settling = storage.list_settling_auctions().await.unwrap();
+        assert_eq!(settling[&auction_id].buyer(), Some("buyer"));
+        assert_eq!(settling[&auction_id].current_price(), 50);
+        assert!(storage.claim_concluded_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_bid_auction_that_does_not_exist() {
+        let storage = create_storage("AuctionsTestBidNonExisting").await;
+        let error = storage
+            .bid_auction(AuctionId::new(), "bidder", 1)
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Auction does not exist");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_close_auction() {
+        let storage = create_storage("AuctionsTestClose").await;
+        let auction = Auction::new("item", 0, Duration::from_secs(100), "seller");
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        assert!(storage.close_auction(auction_id, "other").await.is_err());
+        let closed = storage.close_auction(auction_id, "seller").await.unwrap();
+        assert_eq!(closed.item(), "item");
+        assert!(storage.close_auction(auction_id, "seller").await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_claim_concluded_auctions() {
+        let storage = create_storage("AuctionsTestClaimConcluded").await;
+        let ongoing = Auction::new("item1", 0, Duration::from_secs(100), "seller");
+        let concluded = Auction::new("item2", 0, Duration::from_secs(0), "seller");
+        let ongoing_id = storage.add_auction(ongoing).await.unwrap();
+        let concluded_id = storage.add_auction(concluded).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let claimed = storage.claim_concluded_auctions().await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert!(claimed.contains_key(&concluded_id));
+        assert!(storage
+            .list_ongoing_auctions()
+            .await
+            .unwrap()
+            .contains_key(&ongoing_id));
+        assert!(storage.claim_concluded_auctions().await.unwrap().is_empty());
+        let settling = storage.list_settling_auctions().await.unwrap();
+        assert_eq!(settling.len(), 1);
+        assert!(settling.contains_key(&concluded_id));
+        storage.remove_settled_auction(concluded_id).await.unwrap();
+        assert!(storage.list_settling_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_dutch_auction_is_won_by_first_bid() {
+        let storage = create_storage("AuctionsTestDutch").await;
+        let auction = Auction::new("item", 100, Duration::from_secs(100), "seller").with_type(
+            AuctionType::Dutch(PriceSchedule {
+                floor_price: 40,
+                step: Some(PriceStep {
+                    amount: 10,
+                    interval: Duration::from_secs(1000),
+                }),
+            }),
+        );
+        let auction_id = storage.add_auction(auction.clone()).await.unwrap();
+        assert_eq!(
+            storage
+                .get_auction(auction_id)
+                .await
+                .unwrap()
+                .auction_type(),
+            auction.auction_type()
+        );
+        let placed = storage
+            .bid_auction(auction_id, "bidder", 150)
+            .await
+            .unwrap();
+        assert_eq!(placed.auction.current_price(), 100);
+        assert!(storage.bid_auction(auction_id, "other", 150).await.is_err());
+        let claimed = storage.claim_concluded_auctions().await.unwrap();
+        assert!(claimed.contains_key(&auction_id));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_sealed_bids_are_revised() {
+        let storage = create_storage("AuctionsTestSealedBids").await;
+        let auction = Auction::new("item", 10, Duration::from_secs(100), "seller")
+            .with_type(AuctionType::SealedSecondPrice);
+        let auction_id = storage.add_auction(auction).await.unwrap();
+        storage
+            .bid_auction(auction_id, "bidder1", 30)
+            .await
+            .unwrap();
+        storage
+            .bid_auction(auction_id, "bidder2", 50)
+            .await
+            .unwrap();
+        let placed = storage
+            .bid_auction(auction_id, "bidder1", 40)
+            .await
+            .unwrap();
+        assert_eq!(
+            placed.outbid,
+            Some(Bid {
+                bidder: "bidder1".to_string(),
+                amount: 30
+            })
+        );
+        let mut auction = storage.get_auction(auction_id).await.unwrap();
+        assert_eq!(auction.auction_type(), AuctionType::SealedSecondPrice);
+        assert_eq!(auction.bid_count(), 2);
+        auction.unseal();
+        assert_eq!(auction.buyer(), Some("bidder2"));
+        assert_eq!(auction.current_price(), 40);
+    }
+}
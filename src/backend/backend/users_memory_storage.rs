@@ -1,31 +1,42 @@
 use crate::backend::Funds;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::sync::Mutex;
 
 #[derive(Default)]
 struct UserData {
     funds: Funds,
+    held_funds: Funds,
     items: HashSet<String>,
+    /// Settlement steps which have been applied to the user.
+    settled: HashSet<String>,
 }
 
 #[derive(Default)]
 pub struct UsersMemoryStorage {
-    users: HashMap<String, UserData>,
+    users: Mutex<HashMap<String, UserData>>,
 }
 
+#[tonic::async_trait]
 impl super::UsersBackend for UsersMemoryStorage {
-    fn add_user(&mut self, user: &str) -> Result<(), Box<dyn Error>> {
-        if self.users.contains_key(user) {
+    async fn add_user(&self, user: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut users = self.users.lock().unwrap();
+        if users.contains_key(user) {
             return Err("User already exists".into());
         }
-        self.users.insert(user.to_owned(), UserData::default());
+        users.insert(user.to_owned(), UserData::default());
         Ok(())
     }
 
-    fn deposit_funds(&mut self, user: &str, amount: Funds) -> Result<(), Box<dyn Error>> {
-        if self.users.contains_key(user) {
-            let user_data = self.users.get_mut(user).unwrap();
-            if user_data.funds > Funds::MAX - amount {
+    async fn deposit_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
+            // held funds are released to available funds, so they count towards the limit
+            let total = (user_data.funds + user_data.held_funds).checked_add(amount);
+            if total.is_none() {
                 return Err("Max funds exceeded".into());
             }
             user_data.funds += amount;
@@ -35,9 +46,12 @@ impl super::UsersBackend for UsersMemoryStorage {
         }
     }
 
-    fn withdraw_funds(&mut self, user: &str, amount: Funds) -> Result<(), Box<dyn Error>> {
-        if self.users.contains_key(user) {
-            let user_data = self.users.get_mut(user).unwrap();
+    async fn withdraw_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
             if user_data.funds < amount {
                 return Err("Insufficient funds".into());
             }
@@ -48,9 +62,12 @@ impl super::UsersBackend for UsersMemoryStorage {
         }
     }
 
-    fn deposit_item(&mut self, user: &str, item: &str) -> Result<(), Box<dyn Error>> {
-        if self.users.contains_key(user) {
-            let user_data = self.users.get_mut(user).unwrap();
+    async fn deposit_item(
+        &self,
+        user: &str,
+        item: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
             if user_data.items.contains(item) {
                 return Err("Item already exists".into());
             }
@@ -61,9 +78,12 @@ impl super::UsersBackend for UsersMemoryStorage {
         }
     }
 
-    fn withdraw_item(&mut self, user: &str, item: &str) -> Result<(), Box<dyn Error>> {
-        if self.users.contains_key(user) {
-            let user_data = self.users.get_mut(user).unwrap();
+    async fn withdraw_item(
+        &self,
+        user: &str,
+        item: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
             if !user_data.items.contains(item) {
                 return Err("Item does not exist".into());
             }
@@ -74,23 +94,153 @@ impl super::UsersBackend for UsersMemoryStorage {
         }
     }
 
-    fn list_items(&self, user: &str) -> Result<Vec<String>, Box<dyn Error>> {
-        if self.users.contains_key(user) {
-            let user_data = self.users.get(user).unwrap();
+    async fn list_items(&self, user: &str) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get(user) {
             Ok(user_data.items.iter().cloned().collect())
         } else {
             Err("User does not exist".into())
         }
     }
 
-    fn show_funds(&self, user: &str) -> Result<u32, Box<dyn Error>> {
-        if self.users.contains_key(user) {
-            let user_data = self.users.get(user).unwrap();
+    async fn show_funds(&self, user: &str) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get(user) {
             Ok(user_data.funds)
         } else {
             Err("User does not exist".into())
         }
     }
+
+    async fn hold_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
+            if user_data.funds < amount {
+                return Err("Insufficient funds".into());
+            }
+            user_data.funds -= amount;
+            user_data.held_funds += amount;
+            Ok(())
+        } else {
+            Err("User does not exist".into())
+        }
+    }
+
+    async fn release_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
+            if user_data.held_funds < amount {
+                return Err("Insufficient held funds".into());
+            }
+            user_data.held_funds -= amount;
+            user_data.funds += amount;
+            Ok(())
+        } else {
+            Err("User does not exist".into())
+        }
+    }
+
+    async fn show_held_funds(&self, user: &str) -> Result<Funds, Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get(user) {
+            Ok(user_data.held_funds)
+        } else {
+            Err("User does not exist".into())
+        }
+    }
+
+    async fn release_funds_once(
+        &self,
+        user: &str,
+        amount: Funds,
+        step: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
+            if user_data.settled.contains(step) {
+                return Ok(());
+            }
+            if user_data.held_funds < amount {
+                return Err("Insufficient held funds".into());
+            }
+            user_data.held_funds -= amount;
+            user_data.funds += amount;
+            user_data.settled.insert(step.to_owned());
+            Ok(())
+        } else {
+            Err("User does not exist".into())
+        }
+    }
+
+    async fn deposit_item_once(
+        &self,
+        user: &str,
+        item: &str,
+        step: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(user_data) = self.users.lock().unwrap().get_mut(user) {
+            if user_data.settled.contains(step) {
+                return Ok(());
+            }
+            if user_data.items.contains(item) {
+                return Err("Item already exists".into());
+            }
+            user_data.items.insert(item.to_owned());
+            user_data.settled.insert(step.to_owned());
+            Ok(())
+        } else {
+            Err("User does not exist".into())
+        }
+    }
+
+    async fn settle_sale(
+        &self,
+        seller: &str,
+        buyer: &str,
+        item: &str,
+        price: Funds,
+        settlement: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let bought = format!("{}:bought", settlement);
+        let failed = format!("{}:failed", settlement);
+        let mut users = self.users.lock().unwrap();
+        // everything is checked before the first change, so a failed sale changes nothing
+        let (Some(seller_data), Some(buyer_data)) = (users.get(seller), users.get(buyer)) else {
+            return Err("User does not exist".into());
+        };
+        if buyer_data.settled.contains(&bought) {
+            return Ok(());
+        }
+        if buyer_data.settled.contains(&failed) {
+            return Err("Sale has already failed".into());
+        }
+        let error = if buyer_data.held_funds < price {
+            Some("Insufficient held funds")
+        } else if buyer_data.items.contains(item) {
+            Some("Item already exists")
+        } else if (seller_data.funds + seller_data.held_funds)
+            .checked_add(price)
+            .is_none()
+        {
+            Some("Max funds exceeded")
+        } else {
+            None
+        };
+        let buyer_data = users.get_mut(buyer).unwrap();
+        // the failure is recorded, so a repeated sale cannot take funds held for other bids
+        // after the settlement has refunded this one
+        if let Some(error) = error {
+            buyer_data.settled.insert(failed);
+            return Err(error.into());
+        }
+        buyer_data.held_funds -= price;
+        buyer_data.items.insert(item.to_owned());
+        buyer_data.settled.insert(bought);
+        users.get_mut(seller).unwrap().funds += price;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -98,158 +248,367 @@ mod test {
     use super::*;
     use crate::backend::UsersBackend;
 
-    #[test]
-    fn test_add_user() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        assert!(storage.users.contains_key("user1"));
+    #[tokio::test]
+    async fn test_add_user() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        assert!(storage.users.lock().unwrap().contains_key("user1"));
     }
 
-    #[test]
-    fn test_add_user_twice() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        assert!(storage.add_user("user1").is_err());
+    #[tokio::test]
+    async fn test_add_user_twice() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        assert!(storage.add_user("user1").await.is_err());
     }
 
-    #[test]
-    fn test_deposit_funds() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        assert_eq!(storage.users.get("user1").unwrap().funds, 100);
+    #[tokio::test]
+    async fn test_deposit_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        assert_eq!(
+            storage.users.lock().unwrap().get("user1").unwrap().funds,
+            100
+        );
     }
 
-    #[test]
-    fn test_deposit_funds_twice() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        assert_eq!(storage.users.get("user1").unwrap().funds, 200);
+    #[tokio::test]
+    async fn test_deposit_funds_twice() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        assert_eq!(
+            storage.users.lock().unwrap().get("user1").unwrap().funds,
+            200
+        );
     }
 
-    #[test]
-    fn test_deposit_funds_to_non_existing_user() {
-        let mut storage = UsersMemoryStorage::default();
-        assert!(storage.deposit_funds("user1", 100).is_err());
+    #[tokio::test]
+    async fn test_deposit_funds_to_non_existing_user() {
+        let storage = UsersMemoryStorage::default();
+        assert!(storage.deposit_funds("user1", 100).await.is_err());
     }
 
-    #[test]
-    fn test_deposit_funds_exceeding_max() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        assert!(storage.deposit_funds("user1", Funds::MAX).is_err());
+    #[tokio::test]
+    async fn test_deposit_funds_exceeding_max() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        assert!(storage.deposit_funds("user1", Funds::MAX).await.is_err());
     }
 
-    #[test]
-    fn test_withdraw_funds() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        storage.withdraw_funds("user1", 50).unwrap();
-        assert_eq!(storage.users.get("user1").unwrap().funds, 50);
+    #[tokio::test]
+    async fn test_withdraw_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.withdraw_funds("user1", 50).await.unwrap();
+        assert_eq!(
+            storage.users.lock().unwrap().get("user1").unwrap().funds,
+            50
+        );
     }
 
-    #[test]
-    fn test_withdraw_funds_twice() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        storage.withdraw_funds("user1", 50).unwrap();
-        storage.withdraw_funds("user1", 50).unwrap();
-        assert_eq!(storage.users.get("user1").unwrap().funds, 0);
+    #[tokio::test]
+    async fn test_withdraw_funds_twice() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.withdraw_funds("user1", 50).await.unwrap();
+        storage.withdraw_funds("user1", 50).await.unwrap();
+        assert_eq!(storage.users.lock().unwrap().get("user1").unwrap().funds, 0);
     }
 
-    #[test]
-    fn test_withdraw_funds_from_non_existing_user() {
-        let mut storage = UsersMemoryStorage::default();
-        assert!(storage.withdraw_funds("user1", 100).is_err());
+    #[tokio::test]
+    async fn test_withdraw_funds_from_non_existing_user() {
+        let storage = UsersMemoryStorage::default();
+        assert!(storage.withdraw_funds("user1", 100).await.is_err());
     }
 
-    #[test]
-    fn test_withdraw_funds_exceeding_max() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        assert!(storage.withdraw_funds("user1", Funds::MAX).is_err());
+    #[tokio::test]
+    async fn test_withdraw_funds_exceeding_max() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        assert!(storage.withdraw_funds("user1", Funds::MAX).await.is_err());
     }
 
-    #[test]
-    fn test_withdraw_funds_exceeding_balance() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        assert!(storage.withdraw_funds("user1", 100).is_err());
+    #[tokio::test]
+    async fn test_withdraw_funds_exceeding_balance() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        assert!(storage.withdraw_funds("user1", 100).await.is_err());
     }
 
-    #[test]
-    fn test_deposit_item() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_item("user1", "item1").unwrap();
-        assert!(storage.users.get("user1").unwrap().items.contains("item1"));
+    #[tokio::test]
+    async fn test_deposit_item() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_item("user1", "item1").await.unwrap();
+        assert!(storage
+            .users
+            .lock()
+            .unwrap()
+            .get("user1")
+            .unwrap()
+            .items
+            .contains("item1"));
     }
 
-    #[test]
-    fn test_deposit_item_twice() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_item("user1", "item1").unwrap();
-        assert!(storage.deposit_item("user1", "item1").is_err());
+    #[tokio::test]
+    async fn test_deposit_item_twice() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_item("user1", "item1").await.unwrap();
+        assert!(storage.deposit_item("user1", "item1").await.is_err());
     }
 
-    #[test]
-    fn test_deposit_item_to_non_existing_user() {
-        let mut storage = UsersMemoryStorage::default();
-        assert!(storage.deposit_item("user1", "item1").is_err());
+    #[tokio::test]
+    async fn test_deposit_item_to_non_existing_user() {
+        let storage = UsersMemoryStorage::default();
+        assert!(storage.deposit_item("user1", "item1").await.is_err());
     }
 
-    #[test]
-    fn test_withdraw_item() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_item("user1", "item1").unwrap();
-        storage.withdraw_item("user1", "item1").unwrap();
-        assert!(!storage.users.get("user1").unwrap().items.contains("item1"));
+    #[tokio::test]
+    async fn test_withdraw_item() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_item("user1", "item1").await.unwrap();
+        storage.withdraw_item("user1", "item1").await.unwrap();
+        assert!(!storage
+            .users
+            .lock()
+            .unwrap()
+            .get("user1")
+            .unwrap()
+            .items
+            .contains("item1"));
     }
 
-    #[test]
-    fn test_withdraw_item_twice() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_item("user1", "item1").unwrap();
-        storage.withdraw_item("user1", "item1").unwrap();
-        assert!(storage.withdraw_item("user1", "item1").is_err());
+    #[tokio::test]
+    async fn test_withdraw_item_twice() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_item("user1", "item1").await.unwrap();
+        storage.withdraw_item("user1", "item1").await.unwrap();
+        assert!(storage.withdraw_item("user1", "item1").await.is_err());
     }
 
-    #[test]
-    fn test_withdraw_item_from_non_existing_user() {
-        let mut storage = UsersMemoryStorage::default();
-        assert!(storage.withdraw_item("user1", "item1").is_err());
+    #[tokio::test]
+    async fn test_withdraw_item_from_non_existing_user() {
+        let storage = UsersMemoryStorage::default();
+        assert!(storage.withdraw_item("user1", "item1").await.is_err());
     }
 
-    #[test]
-    fn test_list_items() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_item("user1", "item1").unwrap();
-        storage.deposit_item("user1", "item2").unwrap();
-        let items = storage.list_items("user1").unwrap();
+    #[tokio::test]
+    async fn test_list_items() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_item("user1", "item1").await.unwrap();
+        storage.deposit_item("user1", "item2").await.unwrap();
+        let items = storage.list_items("user1").await.unwrap();
         assert!(items.contains(&"item1".to_owned()));
         assert!(items.contains(&"item2".to_owned()));
     }
 
-    #[test]
-    fn test_show_funds() {
-        let mut storage = UsersMemoryStorage::default();
-        storage.add_user("user1").unwrap();
-        storage.deposit_funds("user1", 100).unwrap();
-        assert_eq!(storage.show_funds("user1").unwrap(), 100);
+    #[tokio::test]
+    async fn test_show_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_show_funds_of_non_existing_user() {
+        let storage = UsersMemoryStorage::default();
+        assert!(storage.show_funds("user1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hold_and_release_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.hold_funds("user1", 70).await.unwrap();
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 30);
+        assert_eq!(storage.show_held_funds("user1").await.unwrap(), 70);
+        assert!(storage.withdraw_funds("user1", 50).await.is_err());
+        storage.release_funds("user1", 20).await.unwrap();
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 50);
+        assert_eq!(storage.show_held_funds("user1").await.unwrap(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_hold_funds_exceeding_balance() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        assert!(storage.hold_funds("user1", 101).await.is_err());
+        assert_eq!(storage.show_held_funds("user1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_release_funds_exceeding_held_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.hold_funds("user1", 10).await.unwrap();
+        assert!(storage.release_funds("user1", 11).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deposit_funds_exceeding_max_with_held_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.hold_funds("user1", 100).await.unwrap();
+        assert!(storage.deposit_funds("user1", Funds::MAX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_settle_sale() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 60).await.unwrap();
+        storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .unwrap();
+        let users = storage.users.lock().unwrap();
+        assert_eq!(users["seller"].funds, 60);
+        assert_eq!(users["buyer"].funds, 40);
+        assert_eq!(users["buyer"].held_funds, 0);
+        assert!(users["buyer"].items.contains("item"));
+    }
+
+    #[tokio::test]
+    async fn test_settle_sale_with_insufficient_held_funds() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 50).await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .is_err());
+        let users = storage.users.lock().unwrap();
+        assert_eq!(users["seller"].funds, 0);
+        assert_eq!(users["buyer"].held_funds, 50);
+        assert!(users["buyer"].items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_settle_sale_of_item_the_buyer_already_has() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 60).await.unwrap();
+        storage.deposit_item("buyer", "item").await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .is_err());
+        let users = storage.users.lock().unwrap();
+        assert_eq!(users["seller"].funds, 0);
+        assert_eq!(users["buyer"].held_funds, 60);
+    }
+
+    #[tokio::test]
+    async fn test_settle_sale_to_non_existing_user() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("seller").await.unwrap();
+        let error = storage
+            .settle_sale("seller", "buyer", "item", 0, "auction")
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "User does not exist");
+    }
+
+    #[tokio::test]
+    async fn test_settle_sale_twice() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 100).await.unwrap();
+        for _ in 0..2 {
+            storage
+                .settle_sale("seller", "buyer", "item", 60, "auction")
+                .await
+                .unwrap();
+        }
+        let users = storage.users.lock().unwrap();
+        assert_eq!(users["seller"].funds, 60);
+        assert_eq!(users["buyer"].held_funds, 40);
+    }
+
+    #[tokio::test]
+    async fn test_failed_sale_is_not_applied_when_repeated() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 100).await.unwrap();
+        storage.deposit_item("buyer", "item").await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .is_err());
+        // the held funds of another bid must not pay for the failed sale
+        storage.withdraw_item("buyer", "item").await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .is_err());
+        let users = storage.users.lock().unwrap();
+        assert_eq!(users["seller"].funds, 0);
+        assert_eq!(users["buyer"].held_funds, 100);
+    }
+
+    #[tokio::test]
+    async fn test_release_funds_once() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.hold_funds("user1", 100).await.unwrap();
+        storage
+            .release_funds_once("user1", 30, "step1")
+            .await
+            .unwrap();
+        storage
+            .release_funds_once("user1", 30, "step1")
+            .await
+            .unwrap();
+        storage
+            .release_funds_once("user1", 30, "step2")
+            .await
+            .unwrap();
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 60);
+        assert_eq!(storage.show_held_funds("user1").await.unwrap(), 40);
     }
 
-    #[test]
-    fn test_show_funds_of_non_existing_user() {
-        let mut storage = UsersMemoryStorage::default();
-        assert!(storage.show_funds("user1").is_err());
+    #[tokio::test]
+    async fn test_deposit_item_once() {
+        let storage = UsersMemoryStorage::default();
+        storage.add_user("user1").await.unwrap();
+        storage
+            .deposit_item_once("user1", "item", "step")
+            .await
+            .unwrap();
+        storage
+            .deposit_item_once("user1", "item", "step")
+            .await
+            .unwrap();
+        assert!(storage
+            .deposit_item_once("user1", "item", "other")
+            .await
+            .is_err());
+        assert_eq!(storage.list_items("user1").await.unwrap(), vec!["item"]);
     }
 }
@@ -0,0 +1,507 @@
+use crate::backend::mongo::is_duplicate_key_error;
+use crate::backend::Funds;
+use mongodb::bson::{doc, Document};
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+const COLLECTION: &str = "Users";
+
+/// Document of the `Users` collection.
+#[derive(Serialize, Deserialize)]
+struct UserDocument {
+    username: String,
+    balance: i64,
+    /// Funds held by bids, they are not a part of the balance.
+    #[serde(default)]
+    held: i64,
+    items: Vec<String>,
+    /// Settlement steps which have been applied to the user.
+    #[serde(default)]
+    settled: Vec<String>,
+}
+
+/// Stores users in MongoDB.
+///
+/// Every change is a single conditional update, so concurrent requests cannot overdraw funds
+/// or withdraw the same item twice. Settlement steps are recorded in the same update as their
+/// change, so a settlement which is repeated after an interruption applies each step once.
+/// A sale changes two users, each side is a step of its own, if crediting the seller fails,
+/// the buyer's step is reverted and the sale recorded as failed.
+pub struct UsersMongoStorage {
+    users: Collection<UserDocument>,
+}
+
+impl UsersMongoStorage {
+    /// Creates the storage and makes sure the unique index on usernames exists.
+    pub async fn new(database: &Database) -> Result<Self, mongodb::error::Error> {
+        let users = database.collection(COLLECTION);
+        let index = IndexModel::builder()
+            .keys(doc! { "username": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        users.create_index(index, None).await?;
+        Ok(Self { users })
+    }
+
+    async fn find_user(&self, user: &str) -> Result<UserDocument, Box<dyn Error + Send + Sync>> {
+        match self.users.find_one(doc! { "username": user }, None).await? {
+            Some(user) => Ok(user),
+            None => Err("User does not exist".into()),
+        }
+    }
+
+    /// Applies the update to the user if the filter matches, otherwise returns the error
+    /// or that the user does not exist.
+    async fn update_user(
+        &self,
+        user: &str,
+        filter: Document,
+        update: Document,
+        error: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut query = doc! { "username": user };
+        query.extend(filter);
+        let result = self.users.update_one(query, update, None).await?;
+        if result.matched_count == 0 {
+            self.find_user(user).await?;
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Applies the update to the user as a settlement step if the filter matches and the step
+    /// has not been applied yet, otherwise returns the same as [`Self::update_user`].
+    async fn update_user_once(
+        &self,
+        user: &str,
+        step: &str,
+        filter: Document,
+        mut update: Document,
+        error: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut query = doc! { "username": user, "settled": { "$ne": step } };
+        query.extend(filter);
+        match update.get_document_mut("$push") {
+            Ok(push) => {
+                push.insert("settled", step);
+            }
+            Err(_) => {
+                update.insert("$push", doc! { "settled": step });
+            }
+        }
+        let result = self.users.update_one(query, update, None).await?;
+        if result.matched_count == 0 && !self.is_settled(user, step).await? {
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Returns whether the settlement step has been applied to the user.
+    async fn is_settled(
+        &self,
+        user: &str,
+        step: &str,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let user = self.find_user(user).await?;
+        Ok(user.settled.iter().any(|settled| settled == step))
+    }
+}
+
+#[tonic::async_trait]
+impl super::UsersBackend for UsersMongoStorage {
+    async fn add_user(&self, user: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let document = UserDocument {
+            username: user.to_owned(),
+            balance: 0,
+            held: 0,
+            items: Vec::new(),
+            settled: Vec::new(),
+        };
+        match self.users.insert_one(document, None).await {
+            Ok(_) => Ok(()),
+            Err(error) if is_duplicate_key_error(&error) => Err("User already exists".into()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn deposit_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let amount = i64::from(amount);
+        self.update_user(
+            user,
+            // held funds are released to the balance, so they count towards the limit
+            doc! {
+                "$expr": {
+                    "$lte": [
+                        { "$add": ["$balance", { "$ifNull": ["$held", 0] }] },
+                        i64::from(Funds::MAX) - amount,
+                    ]
+                }
+            },
+            doc! { "$inc": { "balance": amount } },
+            "Max funds exceeded",
+        )
+        .await
+    }
+
+    async fn withdraw_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let amount = i64::from(amount);
+        self.update_user(
+            user,
+            doc! { "balance": { "$gte": amount } },
+            doc! { "$inc": { "balance": -amount } },
+            "Insufficient funds",
+        )
+        .await
+    }
+
+    async fn deposit_item(
+        &self,
+        user: &str,
+        item: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.update_user(
+            user,
+            doc! { "items": { "$ne": item } },
+            doc! { "$push": { "items": item } },
+            "Item already exists",
+        )
+        .await
+    }
+
+    async fn withdraw_item(
+        &self,
+        user: &str,
+        item: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.update_user(
+            user,
+            doc! { "items": item },
+            doc! { "$pull": { "items": item } },
+            "Item does not exist",
+        )
+        .await
+    }
+
+    async fn list_items(&self, user: &str) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        Ok(self.find_user(user).await?.items)
+    }
+
+    async fn show_funds(&self, user: &str) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        Ok(Funds::try_from(self.find_user(user).await?.balance)?)
+    }
+
+    async fn hold_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let amount = i64::from(amount);
+        self.update_user(
+            user,
+            doc! { "balance": { "$gte": amount } },
+            doc! { "$inc": { "balance": -amount, "held": amount } },
+            "Insufficient funds",
+        )
+        .await
+    }
+
+    async fn release_funds(
+        &self,
+        user: &str,
+        amount: Funds,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let amount = i64::from(amount);
+        self.update_user(
+            user,
+            doc! { "held": { "$gte": amount } },
+            doc! { "$inc": { "balance": amount, "held": -amount } },
+            "Insufficient held funds",
+        )
+        .await
+    }
+
+    async fn show_held_funds(&self, user: &str) -> Result<Funds, Box<dyn Error + Send + Sync>> {
+        Ok(Funds::try_from(self.find_user(user).await?.held)?)
+    }
+
+    async fn release_funds_once(
+        &self,
+        user: &str,
+        amount: Funds,
+        step: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let amount = i64::from(amount);
+        self.update_user_once(
+            user,
+            step,
+            doc! { "held": { "$gte": amount } },
+            doc! { "$inc": { "balance": amount, "held": -amount } },
+            "Insufficient held funds",
+        )
+        .await
+    }
+
+    async fn deposit_item_once(
+        &self,
+        user: &str,
+        item: &str,
+        step: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.update_user_once(
+            user,
+            step,
+            doc! { "items": { "$ne": item } },
+            doc! { "$push": { "items": item } },
+            "Item already exists",
+        )
+        .await
+    }
+
+    async fn settle_sale(
+        &self,
+        seller: &str,
+        buyer: &str,
+        item: &str,
+        price: Funds,
+        settlement: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let price = i64::from(price);
+        let bought = format!("{}:bought", settlement);
+        let sold = format!("{}:sold", settlement);
+        let failed = format!("{}:failed", settlement);
+        // the buyer's side is a single update, which is skipped if the sale is repeated
+        let result = self
+            .users
+            .update_one(
+                doc! {
+                    "username": buyer,
+                    "held": { "$gte": price },
+                    "items": { "$ne": item },
+                    "settled": { "$nin": [&bought, &failed] },
+                },
+                doc! {
+                    "$inc": { "held": -price },
+                    "$push": { "items": item, "settled": &bought },
+                },
+                None,
+            )
+            .await?;
+        if result.matched_count == 0 {
+            let buyer_document = self.find_user(buyer).await?;
+            if buyer_document.settled.contains(&failed) {
+                return Err("Sale has already failed".into());
+            }
+            if !buyer_document.settled.contains(&bought) {
+                // the failure is recorded, so a repeated sale cannot take funds held for other bids
+                // after the settlement has refunded this one
+                self.users
+                    .update_one(
+                        doc! { "username": buyer, "settled": { "$nin": [&bought, &failed] } },
+                        doc! { "$push": { "settled": &failed } },
+                        None,
+                    )
+                    .await?;
+                if buyer_document.items.iter().any(|owned| owned == item) {
+                    return Err("Item already exists".into());
+                }
+                return Err("Insufficient held funds".into());
+            }
+        }
+        let credited = self
+            .update_user_once(
+                seller,
+                &sold,
+                doc! {
+                    "$expr": {
+                        "$lte": [
+                            { "$add": ["$balance", { "$ifNull": ["$held", 0] }] },
+                            i64::from(Funds::MAX) - price,
+                        ]
+                    }
+                },
+                doc! { "$inc": { "balance": price } },
+                "Max funds exceeded",
+            )
+            .await;
+        if let Err(error) = credited {
+            self.users
+                .update_one(
+                    doc! { "username": buyer, "settled": { "$all": [&bought], "$nin": [&failed] } },
+                    doc! {
+                        "$inc": { "held": price },
+                        "$pull": { "items": item },
+                        "$push": { "settled": &failed },
+                    },
+                    None,
+                )
+                .await?;
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+/// The tests require a running MongoDB, run them with `cargo test -- --ignored`.
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::mongo::test_database;
+    use crate::backend::UsersBackend;
+
+    async fn create_storage(name: &str) -> UsersMongoStorage {
+        UsersMongoStorage::new(&test_database(name).await)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_add_user_twice() {
+        let storage = create_storage("UsersTestAddUserTwice").await;
+        storage.add_user("user1").await.unwrap();
+        assert!(storage.add_user("user1").await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_deposit_and_withdraw_funds() {
+        let storage = create_storage("UsersTestFunds").await;
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.withdraw_funds("user1", 40).await.unwrap();
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 60);
+        assert!(storage.withdraw_funds("user1", 61).await.is_err());
+        assert!(storage.deposit_funds("user1", Funds::MAX).await.is_err());
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 60);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_deposit_funds_to_non_existing_user() {
+        let storage = create_storage("UsersTestNonExistingUser").await;
+        let error = storage.deposit_funds("user1", 100).await.unwrap_err();
+        assert_eq!(error.to_string(), "User does not exist");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_deposit_and_withdraw_items() {
+        let storage = create_storage("UsersTestItems").await;
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_item("user1", "item1").await.unwrap();
+        storage.deposit_item("user1", "item2").await.unwrap();
+        assert!(storage.deposit_item("user1", "item1").await.is_err());
+        storage.withdraw_item("user1", "item1").await.unwrap();
+        assert!(storage.withdraw_item("user1", "item1").await.is_err());
+        assert_eq!(storage.list_items("user1").await.unwrap(), vec!["item2"]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_hold_and_release_funds() {
+        let storage = create_storage("UsersTestHeldFunds").await;
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.hold_funds("user1", 70).await.unwrap();
+        assert!(storage.hold_funds("user1", 31).await.is_err());
+        assert!(storage.withdraw_funds("user1", 31).await.is_err());
+        storage.release_funds("user1", 20).await.unwrap();
+        assert!(storage.release_funds("user1", 51).await.is_err());
+        assert_eq!(storage.show_funds("user1").await.unwrap(), 50);
+        assert_eq!(storage.show_held_funds("user1").await.unwrap(), 50);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_settle_sale() {
+        let storage = create_storage("UsersTestSettleSale").await;
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 60).await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 61, "auction1")
+            .await
+            .is_err());
+        storage
+            .settle_sale("seller", "buyer", "item", 60, "auction2")
+            .await
+            .unwrap();
+        assert_eq!(storage.show_funds("seller").await.unwrap(), 60);
+        assert_eq!(storage.show_held_funds("buyer").await.unwrap(), 0);
+        assert_eq!(storage.list_items("buyer").await.unwrap(), vec!["item"]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_settle_sale_twice() {
+        let storage = create_storage("UsersTestSettleSaleTwice").await;
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 100).await.unwrap();
+        for _ in 0..2 {
+            storage
+                .settle_sale("seller", "buyer", "item", 60, "auction")
+                .await
+                .unwrap();
+        }
+        assert_eq!(storage.show_funds("seller").await.unwrap(), 60);
+        assert_eq!(storage.show_held_funds("buyer").await.unwrap(), 40);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_failed_sale_is_reverted_and_not_repeated() {
+        let storage = create_storage("UsersTestFailedSale").await;
+        storage.add_user("seller").await.unwrap();
+        storage.add_user("buyer").await.unwrap();
+        storage.deposit_funds("seller", Funds::MAX).await.unwrap();
+        storage.deposit_funds("buyer", 100).await.unwrap();
+        storage.hold_funds("buyer", 100).await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .is_err());
+        assert_eq!(storage.show_held_funds("buyer").await.unwrap(), 100);
+        assert!(storage.list_items("buyer").await.unwrap().is_empty());
+        storage.withdraw_funds("seller", Funds::MAX).await.unwrap();
+        assert!(storage
+            .settle_sale("seller", "buyer", "item", 60, "auction")
+            .await
+            .is_err());
+        assert_eq!(storage.show_funds("seller").await.unwrap(), 0);
+        assert_eq!(storage.show_held_funds("buyer").await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_release_funds_and_deposit_item_once() {
+        let storage = create_storage("UsersTestSettlementSteps").await;
+        storage.add_user("user1").await.unwrap();
+        storage.deposit_funds("user1", 100).await.unwrap();
+        storage.hold_funds("user1", 100).await.unwrap();
+        for _ in 0..2 {
+            storage
+                .release_funds_once("user1", 30, "release")
+                .await
+                .unwrap();
+            storage
+                .deposit_item_once("user1", "item", "return")
+                .await
+                .unwrap();
+        }
+        assert_eq!(storage.show_held_funds("user1").await.unwrap(), 70);
+        assert_eq!(storage.list_items("user1").await.unwrap(), vec!["item"]);
+    }
+}
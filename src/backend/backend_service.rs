@@ -1,12 +1,17 @@
 use backend_proto::backend_server::Backend;
 use backend_proto::{
-    BidItemRequest, DepositFundsRequest, DepositItemRequest, ListAuctionsResponse, SellItemRequest,
-    ShowFundsResponse, ShowItemsResponse, WatchUserAuctionsResponse, WithdrawFundsRequest,
-    WithdrawItemRequest,
+    auction_change, sell_item_request, AuctionChange, BidItemRequest, BuyNowRequest,
+    CloseAuctionRequest, DepositFundsRequest, DepositItemRequest, ListAuctionsResponse,
+    SellItemRequest, ShowFundsResponse, ShowItemsResponse, WatchAuctionChangesRequest,
+    WatchUserAuctionsResponse, WithdrawFundsRequest, WithdrawItemRequest,
 };
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::Stream;
 use tonic::{Request, Response, Status};
 pub mod backend_proto {
@@ -15,18 +20,30 @@ pub mod backend_proto {
 
 use crate::backend::{
     auctions_memory_storage::AuctionsMemoryStorage, users_memory_storage::UsersMemoryStorage,
-    AuctionsBackend, UsersBackend,
+    Auction, AuctionId, AuctionType, AuctionsBackend, Bid, BidIncrement, Funds, PlacedBid,
+    PriceSchedule, PriceStep, UsersBackend,
 };
+use crate::events::{AuctionEvent, EventBus, SequencedEvent};
+use crate::scheduler::Scheduler;
+use crate::settlement::{Outcome, Settlement};
 
 type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
 
+/// How many responses are buffered for a watching client before events are no longer received.
+const STREAM_BUFFER: usize = 16;
+
+/// The longest duration in seconds accepted from clients, it keeps end times far from overflowing.
+const MAX_DURATION: u64 = 10 * 365 * 24 * 60 * 60;
+
 pub struct BackendService<UBT, ABT>
 where
     UBT: UsersBackend + Send + 'static,
     ABT: AuctionsBackend + Send + 'static,
 {
-    users: Arc<Mutex<UBT>>,
-    auctions: Arc<Mutex<ABT>>,
+    users: Arc<UBT>,
+    auctions: Arc<ABT>,
+    scheduler: Option<Scheduler>,
+    events: EventBus,
 }
 
 impl<UBT, ABT> Default for BackendService<UBT, ABT>
@@ -36,14 +53,530 @@ where
 {
     fn default() -> Self {
         Self {
-            users: Arc::new(Mutex::new(UBT::default())),
-            auctions: Arc::new(Mutex::new(ABT::default())),
+            users: Arc::new(UBT::default()),
+            auctions: Arc::new(ABT::default()),
+            scheduler: None,
+            events: EventBus::default(),
         }
     }
 }
 
+impl<UBT, ABT> BackendService<UBT, ABT>
+where
+    UBT: UsersBackend + Send + 'static,
+    ABT: AuctionsBackend + Send + 'static,
+{
+    pub fn new(users: UBT, auctions: ABT) -> Self {
+        Self {
+            users: Arc::new(users),
+            auctions: Arc::new(auctions),
+            scheduler: None,
+            events: EventBus::default(),
+        }
+    }
+
+    /// Settles auctions with the given scheduler when they end,
+    /// without it concluded auctions are kept in the storage.
+    pub fn with_scheduler(self, scheduler: Scheduler) -> Self {
+        Self {
+            scheduler: Some(scheduler),
+            ..self
+        }
+    }
+
+    /// Returns the bus of the events published by the service.
+    pub fn events(&self) -> EventBus {
+        self.events.clone()
+    }
+
+    /// Returns the settlement of concluded auctions sharing the service's storages.
+    pub fn settlement(&self) -> Settlement<UBT, ABT> {
+        Settlement::new(self.users.clone(), self.auctions.clone())
+    }
+}
+
 pub type DefaultBackendService = BackendService<UsersMemoryStorage, AuctionsMemoryStorage>;
 
+/// Request extension carrying the name of the user on whose behalf the request is executed.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser(pub String);
+
+#[allow(clippy::result_large_err)]
+fn get_user<T>(request: &Request<T>) -> Result<String, Status> {
+    match request.extensions().get::<AuthenticatedUser>() {
+        Some(AuthenticatedUser(user)) => Ok(user.clone()),
+        None => Err(Status::new(
+            tonic::Code::Unauthenticated,
+            "User is not authenticated".to_string(),
+        )),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn to_funds(amount: u64) -> Result<Funds, Status> {
+    Funds::try_from(amount).map_err(|_| {
+        Status::new(
+            tonic::Code::InvalidArgument,
+            format!("Amount cannot exceed {}", Funds::MAX),
+        )
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn to_bid_increment(
+    min_increment: Option<sell_item_request::MinIncrement>,
+) -> Result<BidIncrement, Status> {
+    let min_increment = match min_increment {
+        None => return Ok(BidIncrement::default()),
+        Some(sell_item_request::MinIncrement::MinIncrementAmount(amount)) => {
+            BidIncrement::Amount(to_funds(amount)?)
+        }
+        Some(sell_item_request::MinIncrement::MinIncrementPercent(percent)) => {
+            BidIncrement::Percent(percent)
+        }
+    };
+    if matches!(
+        min_increment,
+        BidIncrement::Amount(0) | BidIncrement::Percent(0)
+    ) {
+        return Err(Status::new(
+            tonic::Code::InvalidArgument,
+            "Minimum bid increment must be greater than 0".to_string(),
+        ));
+    }
+    Ok(min_increment)
+}
+
+#[allow(clippy::result_large_err)]
+fn to_auction_type(data: &SellItemRequest) -> Result<AuctionType, Status> {
+    let invalid = |message: &str| Status::new(tonic::Code::InvalidArgument, message.to_string());
+    match backend_proto::AuctionType::try_from(data.auction_type) {
+        Ok(backend_proto::AuctionType::English) => Ok(AuctionType::English),
+        Ok(
+            auction_type @ (backend_proto::AuctionType::SealedFirstPrice
+            | backend_proto::AuctionType::SealedSecondPrice),
+        ) => {
+            // bids are not known until the end, so there is nothing to outbid, extend or buy at once
+            if data.min_increment.is_some()
+                || data.buy_now_price.is_some()
+                || data.soft_close.is_some()
+            {
+                return Err(invalid(
+                    "Sealed-bid auctions cannot have a minimum increment, buy now price or soft close",
+                ));
+            }
+            Ok(match auction_type {
+                backend_proto::AuctionType::SealedFirstPrice => AuctionType::SealedFirstPrice,
+                _ => AuctionType::SealedSecondPrice,
+            })
+        }
+        Ok(backend_proto::AuctionType::Dutch) => {
+            let Some(schedule) = &data.price_schedule else {
+                return Err(invalid("Dutch auctions require a price schedule"));
+            };
+            // the price only drops, so there is nothing to outbid, extend or buy at a fixed price
+            if data.min_increment.is_some()
+                || data.reserve_price.is_some()
+                || data.buy_now_price.is_some()
+                || data.soft_close.is_some()
+            {
+                return Err(invalid(
+                    "Dutch auctions cannot have a minimum increment, reserve price, buy now price or soft close",
+                ));
+            }
+            if schedule.floor_price > data.price {
+                return Err(invalid(
+                    "Floor price cannot be higher than the starting price",
+                ));
+            }
+            let step = match &schedule.step {
+                Some(step) if step.amount == 0 || step.interval == 0 => {
+                    return Err(invalid(
+                        "Price step amount and interval must be greater than 0",
+                    ));
+                }
+                Some(step) => Some(PriceStep {
+                    amount: to_funds(step.amount)?,
+                    interval: Duration::from_secs(step.interval),
+                }),
+                None => None,
+            };
+            Ok(AuctionType::Dutch(PriceSchedule {
+                floor_price: to_funds(schedule.floor_price)?,
+                step,
+            }))
+        }
+        Err(_) => Err(invalid("Unknown auction type")),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_auction_id(auction_id: &str) -> Result<AuctionId, Status> {
+    auction_id.parse().map_err(|_| {
+        Status::new(
+            tonic::Code::InvalidArgument,
+            format!("Invalid auction id: {}", auction_id),
+        )
+    })
+}
+
+fn to_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Maps an error returned by a storage backend to a gRPC status.
+fn storage_error_to_status(error: Box<dyn std::error::Error + Send + Sync>) -> Status {
+    let message = error.to_string();
+    let lowercase = message.to_lowercase();
+    let code = if lowercase.contains("does not exist") {
+        tonic::Code::NotFound
+    } else if lowercase.contains("already exists") {
+        tonic::Code::AlreadyExists
+    } else if lowercase.contains("only the seller") {
+        tonic::Code::PermissionDenied
+    } else if lowercase.contains("lower than") || lowercase.contains("not accepted") {
+        tonic::Code::InvalidArgument
+    } else if lowercase.contains("insufficient")
+        || lowercase.contains("exceeded")
+        || lowercase.contains("concluded")
+        || lowercase.contains("highest bidder")
+        || lowercase.contains("cannot bid")
+        || lowercase.contains("cannot buy")
+        || lowercase.contains("not offered")
+    {
+        tonic::Code::FailedPrecondition
+    } else {
+        tonic::Code::Internal
+    };
+    Status::new(code, message)
+}
+
+fn auction_to_proto(auction_id: &AuctionId, auction: &Auction) -> backend_proto::Auction {
+    let price = if auction.buyer().is_some() {
+        auction.current_price()
+    } else {
+        auction.price_at(SystemTime::now())
+    };
+    backend_proto::Auction {
+        id: auction_id.to_string(),
+        item: auction.item().to_owned(),
+        price: price.into(),
+        seller: auction.seller().to_owned(),
+        buyer: auction.buyer().unwrap_or_default().to_owned(),
+        created_at: to_timestamp(auction.start_time()),
+        ends_at: to_timestamp(auction.end_time()),
+        min_bid: auction.min_bid().into(),
+        reserve_met: auction
+            .reserve_price()
+            .filter(|_| !auction.is_sealed())
+            .map(|_| auction.reserve_met()),
+        buy_now_price: auction.buy_now_price().map(Into::into),
+        auction_type: match auction.auction_type() {
+            AuctionType::English => backend_proto::AuctionType::English,
+            AuctionType::Dutch(_) => backend_proto::AuctionType::Dutch,
+            AuctionType::SealedFirstPrice => backend_proto::AuctionType::SealedFirstPrice,
+            AuctionType::SealedSecondPrice => backend_proto::AuctionType::SealedSecondPrice,
+        }
+        .into(),
+        bid_count: auction
+            .is_sealed()
+            .then(|| u32::try_from(auction.bid_count()).unwrap_or(u32::MAX)),
+        version: auction.version(),
+    }
+}
+
+/// Returns the ongoing auctions ordered by their end times.
+async fn list_ongoing_auctions<ABT>(auctions: &ABT) -> Result<Vec<backend_proto::Auction>, Status>
+where
+    ABT: AuctionsBackend,
+{
+    let mut auctions: Vec<_> = auctions
+        .list_ongoing_auctions()
+        .await
+        .map_err(storage_error_to_status)?
+        .iter()
+        .map(|(auction_id, auction)| auction_to_proto(auction_id, auction))
+        .collect();
+    auctions.sort_by(|lhs, rhs| lhs.ends_at.cmp(&rhs.ends_at).then(lhs.id.cmp(&rhs.id)));
+    Ok(auctions)
+}
+
+/// Sends the ongoing auctions whenever they change, until the client disconnects.
+async fn send_auctions<ABT>(
+    auctions: Arc<ABT>,
+    mut events: broadcast::Receiver<SequencedEvent>,
+    sender: mpsc::Sender<Result<ListAuctionsResponse, Status>>,
+) where
+    ABT: AuctionsBackend,
+{
+    loop {
+        match events.recv().await.map(|event| event.event) {
+            // the auctions are unchanged, the new price and end time come with `BidPlaced`,
+            // the removal with `Expired`
+            Ok(
+                AuctionEvent::Outbid { .. }
+                | AuctionEvent::Extended { .. }
+                | AuctionEvent::Settled { .. },
+            ) => continue,
+            // a lagging stream sends the current auctions, so missed events are not lost
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+        let response = list_ongoing_auctions(auctions.as_ref())
+            .await
+            .map(|auctions| ListAuctionsResponse { auctions });
+        if sender.send(response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Sends the user's auctions whenever they change, until the client disconnects.
+///
+/// The user's auctions are those sold by the user and those the user has bid on,
+/// they are watched until they are closed or settled.
+async fn send_user_auctions<ABT>(
+    auctions: Arc<ABT>,
+    user: String,
+    mut watched: HashSet<String>,
+    mut events: broadcast::Receiver<SequencedEvent>,
+    sender: mpsc::Sender<Result<WatchUserAuctionsResponse, Status>>,
+) where
+    ABT: AuctionsBackend,
+{
+    loop {
+        let mut response = WatchUserAuctionsResponse::default();
+        match events.recv().await.map(|event| event.event) {
+            Ok(event) => {
+                let auction_id = event.auction_id().to_string();
+                match &event {
+                    // every bidder is recorded, also those outbid at once by a proxy bid
+                    AuctionEvent::Created { auction, .. }
+                    | AuctionEvent::BidPlaced { auction, .. }
+                        if auction.involves(&user) =>
+                    {
+                        watched.insert(auction_id.clone());
+                    }
+                    AuctionEvent::Bought { bid, .. } if bid.bidder == user => {
+                        watched.insert(auction_id.clone());
+                    }
+                    _ => {}
+                }
+                if !watched.contains(&auction_id) {
+                    continue;
+                }
+                match event {
+                    // the user is notified about the new highest bid
+                    AuctionEvent::Outbid { .. } => continue,
+                    AuctionEvent::Extended { .. } => response.extended_auctions.push(auction_id),
+                    AuctionEvent::Expired { .. } => response.expired_auctions.push(auction_id),
+                    AuctionEvent::Closed { .. } | AuctionEvent::Settled { .. } => {
+                        watched.remove(&auction_id);
+                        if let AuctionEvent::Settled {
+                            outcome: Outcome::ReserveNotMet(_),
+                            ..
+                        } = event
+                        {
+                            response.reserve_not_met_auctions.push(auction_id.clone());
+                        }
+                        response.finalized_auctions.push(auction_id);
+                    }
+                    _ => {}
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+        let response = list_ongoing_auctions(auctions.as_ref())
+            .await
+            .map(|auctions| WatchUserAuctionsResponse {
+                auctions: auctions
+                    .into_iter()
+                    .filter(|auction| watched.contains(&auction.id))
+                    .collect(),
+                ..response
+            });
+        if sender.send(response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Returns the change of the ongoing auctions made by the event, if any.
+fn event_to_change(event: &AuctionEvent) -> Option<auction_change::Change> {
+    match event {
+        AuctionEvent::Created {
+            auction_id,
+            auction,
+        } => Some(auction_change::Change::Created(auction_to_proto(
+            auction_id, auction,
+        ))),
+        AuctionEvent::BidPlaced {
+            auction_id,
+            auction,
+            ..
+        } => Some(auction_change::Change::Updated(auction_to_proto(
+            auction_id, auction,
+        ))),
+        AuctionEvent::Bought { auction_id, .. }
+        | AuctionEvent::Closed { auction_id, .. }
+        | AuctionEvent::Expired { auction_id, .. } => {
+            Some(auction_change::Change::Removed(auction_id.to_string()))
+        }
+        // the new end time of an extended auction comes with the update of the bid
+        AuctionEvent::Outbid { .. }
+        | AuctionEvent::Extended { .. }
+        | AuctionEvent::Settled { .. } => None,
+    }
+}
+
+/// Sends the change made by the event, unless it has been sent already.
+/// # Returns
+/// `false` if the client has disconnected.
+async fn send_change(
+    sender: &mpsc::Sender<Result<AuctionChange, Status>>,
+    event: SequencedEvent,
+    last_sent: &mut u64,
+) -> bool {
+    if event.sequence <= *last_sent {
+        return true;
+    }
+    *last_sent = event.sequence;
+    let Some(change) = event_to_change(&event.event) else {
+        return true;
+    };
+    let change = AuctionChange {
+        sequence: event.sequence,
+        change: Some(change),
+    };
+    sender.send(Ok(change)).await.is_ok()
+}
+
+/// Subscribes to the events and sends the snapshot of the ongoing auctions.
+///
+/// The snapshot is taken after subscribing, so it may already contain some of the changes
+/// which come after it, applying them again does not change the result.
+/// # Returns
+/// The receiver of the events or `None` if the snapshot could not be sent.
+async fn send_snapshot<ABT>(
+    auctions: &ABT,
+    events: &EventBus,
+    sender: &mpsc::Sender<Result<AuctionChange, Status>>,
+    last_sent: &mut u64,
+) -> Option<broadcast::Receiver<SequencedEvent>>
+where
+    ABT: AuctionsBackend,
+{
+    let (sequence, receiver) = events.subscribe_with_sequence();
+    let snapshot = list_ongoing_auctions(auctions).await;
+    let failed = snapshot.is_err();
+    let snapshot = snapshot.map(|auctions| AuctionChange {
+        sequence,
+        change: Some(auction_change::Change::Snapshot(ListAuctionsResponse {
+            auctions,
+        })),
+    });
+    if sender.send(snapshot).await.is_err() || failed {
+        return None;
+    }
+    *last_sent = sequence;
+    Some(receiver)
+}
+
+/// Sends changes of the ongoing auctions, until the client disconnects.
+///
+/// The changes start with a snapshot, unless the events after `last_sequence` are still kept.
+async fn send_auction_changes<ABT>(
+    auctions: Arc<ABT>,
+    events: EventBus,
+    last_sequence: Option<u64>,
+    sender: mpsc::Sender<Result<AuctionChange, Status>>,
+) where
+    ABT: AuctionsBackend,
+{
+    let mut last_sent = last_sequence.unwrap_or_default();
+    let resumed = last_sequence.and_then(|sequence| events.resume(sequence));
+    let mut receiver = match resumed {
+        Some((missed, receiver)) => {
+            for event in missed {
+                if !send_change(&sender, event, &mut last_sent).await {
+                    return;
+                }
+            }
+            receiver
+        }
+        None => match send_snapshot(auctions.as_ref(), &events, &sender, &mut last_sent).await {
+            Some(receiver) => receiver,
+            None => return,
+        },
+    };
+    loop {
+        let sent = match receiver.recv().await {
+            Ok(event) => send_change(&sender, event, &mut last_sent).await,
+            // a lagging stream starts over with a new snapshot
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                match send_snapshot(auctions.as_ref(), &events, &sender, &mut last_sent).await {
+                    Some(new_receiver) => {
+                        receiver = new_receiver;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => false,
+        };
+        if !sent {
+            return;
+        }
+    }
+}
+
+impl<UBT, ABT> BackendService<UBT, ABT>
+where
+    UBT: UsersBackend + Send + 'static,
+    ABT: AuctionsBackend + Send + 'static,
+{
+    /// Releases funds held by a bid which is no longer the highest one.
+    async fn release_funds(&self, user: &str, amount: Funds) -> Result<(), Status> {
+        self.users
+            .release_funds(user, amount)
+            .await
+            .map_err(storage_error_to_status)
+    }
+
+    /// Returns an item held by an auction back to the seller's inventory.
+    async fn return_item(&self, seller: &str, item: &str) -> Result<(), Status> {
+        self.users
+            .deposit_item(seller, item)
+            .await
+            .map_err(storage_error_to_status)
+    }
+
+    /// Returns the authenticated user of the request, the user's account is created on the first use.
+    async fn get_or_add_user<T>(&self, request: &Request<T>) -> Result<String, Status> {
+        let user = get_user(request)?;
+        let Err(error) = self.users.show_funds(&user).await else {
+            return Ok(user);
+        };
+        let status = storage_error_to_status(error);
+        if status.code() != tonic::Code::NotFound {
+            return Err(status);
+        }
+        // concurrent first requests of the user race to create the account, one of them wins
+        match self
+            .users
+            .add_user(&user)
+            .await
+            .map_err(storage_error_to_status)
+        {
+            Err(status) if status.code() != tonic::Code::AlreadyExists => Err(status),
+            _ => Ok(user),
+        }
+    }
+}
+
 #[tonic::async_trait]
 impl<UBT, ABT> Backend for BackendService<UBT, ABT>
 where
@@ -54,65 +587,450 @@ where
         &self,
         request: Request<DepositFundsRequest>,
     ) -> Result<Response<()>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let amount = to_funds(request.into_inner().amount)?;
+        self.users
+            .deposit_funds(&user, amount)
+            .await
+            .map_err(storage_error_to_status)?;
+        Ok(Response::new(()))
     }
 
     async fn withdraw_funds(
         &self,
         request: Request<WithdrawFundsRequest>,
     ) -> Result<Response<()>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let amount = to_funds(request.into_inner().amount)?;
+        self.users
+            .withdraw_funds(&user, amount)
+            .await
+            .map_err(storage_error_to_status)?;
+        Ok(Response::new(()))
     }
 
     async fn show_funds(
         &self,
         request: Request<()>,
     ) -> Result<Response<ShowFundsResponse>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let funds = self
+            .users
+            .show_funds(&user)
+            .await
+            .map_err(storage_error_to_status)?;
+        let held_funds = self
+            .users
+            .show_held_funds(&user)
+            .await
+            .map_err(storage_error_to_status)?;
+        Ok(Response::new(ShowFundsResponse {
+            funds: funds.into(),
+            held_funds: held_funds.into(),
+        }))
     }
 
     async fn deposit_item(
         &self,
         request: Request<DepositItemRequest>,
     ) -> Result<Response<()>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let item = request.into_inner().item;
+        if item.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Item name cannot be empty".to_string(),
+            ));
+        }
+        self.users
+            .deposit_item(&user, &item)
+            .await
+            .map_err(storage_error_to_status)?;
+        Ok(Response::new(()))
     }
 
     async fn withdraw_item(
         &self,
         request: Request<WithdrawItemRequest>,
     ) -> Result<Response<()>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let item = request.into_inner().item;
+        self.users
+            .withdraw_item(&user, &item)
+            .await
+            .map_err(storage_error_to_status)?;
+        Ok(Response::new(()))
     }
 
     async fn show_items(
         &self,
         request: Request<()>,
     ) -> Result<Response<ShowItemsResponse>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let mut items = self
+            .users
+            .list_items(&user)
+            .await
+            .map_err(storage_error_to_status)?;
+        items.sort();
+        Ok(Response::new(ShowItemsResponse { items }))
     }
 
-    async fn sell_item(&self, request: Request<SellItemRequest>) -> Result<Response<()>, Status> {
-        unimplemented!()
+    async fn sell_item(
+        &self,
+        request: Request<SellItemRequest>,
+    ) -> Result<Response<backend_proto::Auction>, Status> {
+        let user = self.get_or_add_user(&request).await?;
+        let data = request.into_inner();
+        if data.item.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Item name cannot be empty".to_string(),
+            ));
+        }
+        if data.duration == 0 {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Auction duration must be greater than 0".to_string(),
+            ));
+        }
+        if data.duration > MAX_DURATION {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                format!("Auction duration cannot exceed {} seconds", MAX_DURATION),
+            ));
+        }
+        let auction_type = to_auction_type(&data)?;
+        let auction = Auction::new(
+            &data.item,
+            to_funds(data.price)?,
+            Duration::from_secs(data.duration),
+            &user,
+        )
+        .with_min_increment(to_bid_increment(data.min_increment)?)
+        .with_type(auction_type);
+        let auction = match data.reserve_price {
+            Some(reserve_price) => auction.with_reserve_price(to_funds(reserve_price)?),
+            None => auction,
+        };
+        let auction = match data.soft_close {
+            Some(soft_close) => {
+                if soft_close.window == 0 || soft_close.extension == 0 {
+                    return Err(Status::new(
+                        tonic::Code::InvalidArgument,
+                        "Soft close window and extension must be greater than 0".to_string(),
+                    ));
+                }
+                if soft_close.window > MAX_DURATION
+                    || soft_close.extension > MAX_DURATION
+                    || soft_close.max_extension.unwrap_or_default() > MAX_DURATION
+                {
+                    return Err(Status::new(
+                        tonic::Code::InvalidArgument,
+                        format!(
+                            "Soft close window and extensions cannot exceed {} seconds",
+                            MAX_DURATION
+                        ),
+                    ));
+                }
+                auction.with_soft_close(
+                    Duration::from_secs(soft_close.window),
+                    Duration::from_secs(soft_close.extension),
+                    soft_close.max_extension.map(Duration::from_secs),
+                )
+            }
+            None => auction,
+        };
+        let auction = match data.buy_now_price {
+            Some(buy_now_price) => {
+                let buy_now_price = to_funds(buy_now_price)?;
+                if buy_now_price < auction.starting_price()
+                    || buy_now_price < auction.reserve_price().unwrap_or_default()
+                {
+                    return Err(Status::new(
+                        tonic::Code::InvalidArgument,
+                        "Buy now price cannot be lower than the starting price or the reserve price"
+                            .to_string(),
+                    ));
+                }
+                auction.with_buy_now_price(buy_now_price)
+            }
+            None => auction,
+        };
+        // the item stays out of the seller's inventory while the auction runs
+        self.users
+            .withdraw_item(&user, &data.item)
+            .await
+            .map_err(storage_error_to_status)?;
+        let auction_id = match self.auctions.add_auction(auction.clone()).await {
+            Ok(auction_id) => auction_id,
+            Err(error) => {
+                self.return_item(&user, &data.item).await?;
+                return Err(storage_error_to_status(error));
+            }
+        };
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.schedule(auction.end_time());
+        }
+        let created = auction_to_proto(&auction_id, &auction);
+        self.events.publish(AuctionEvent::Created {
+            auction_id,
+            auction,
+        });
+        Ok(Response::new(created))
     }
 
     async fn bid_item(&self, request: Request<BidItemRequest>) -> Result<Response<()>, Status> {
-        unimplemented!()
+        let user = self.get_or_add_user(&request).await?;
+        let data = request.into_inner();
+        let auction_id = parse_auction_id(&data.auction_id)?;
+        let amount = to_funds(data.price)?;
+        // a sealed bid replaces the bidder's previous one, which is already held
+        let previous = match self.auctions.get_auction(auction_id).await {
+            Ok(auction) if auction.is_sealed() => auction
+                .held_bids()
+                .into_iter()
+                .find(|bid| bid.bidder == user)
+                .map_or(0, |bid| bid.amount),
+            _ => 0,
+        };
+        // the bid is held first, so the bidder can always pay if they win
+        let held = amount.saturating_sub(previous);
+        self.users
+            .hold_funds(&user, held)
+            .await
+            .map_err(storage_error_to_status)?;
+        let placed = if data.proxy {
+            self.auctions
+                .proxy_bid_auction(auction_id, &user, amount)
+                .await
+        } else {
+            self.auctions.bid_auction(auction_id, &user, amount).await
+        };
+        let PlacedBid {
+            auction,
+            outbid,
+            extended,
+        } = match placed {
+            Ok(placed) => placed,
+            Err(error) => {
+                self.release_funds(&user, held).await?;
+                return Err(storage_error_to_status(error));
+            }
+        };
+        // an extended auction ends later and a Dutch auction ends with its first bid
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.schedule(auction.end_time());
+        }
+        if auction.is_sealed() {
+            // the replaced bid's funds together with the held ones cover the new bid, the rest
+            // is released, the replaced bid differs from the previous one only if the bidder
+            // has revised it concurrently
+            let covered = u64::from(outbid.map_or(0, |replaced| replaced.amount)) + u64::from(held);
+            match covered.cmp(&u64::from(amount)) {
+                Ordering::Greater => {
+                    self.release_funds(&user, to_funds(covered - u64::from(amount))?)
+                        .await?
+                }
+                Ordering::Less => self
+                    .users
+                    .hold_funds(&user, to_funds(u64::from(amount) - covered)?)
+                    .await
+                    .map_err(storage_error_to_status)?,
+                Ordering::Equal => {}
+            }
+            self.events.publish(AuctionEvent::BidPlaced {
+                auction_id,
+                auction,
+                bid: Bid {
+                    bidder: user,
+                    amount,
+                },
+            });
+            return Ok(Response::new(()));
+        }
+        // the highest bid is published with its price, a proxy bid's maximum stays hidden
+        let highest = Bid {
+            bidder: auction.buyer().unwrap_or_default().to_owned(),
+            amount: auction.current_price(),
+        };
+        let outbid_at_once = highest.bidder != user;
+        self.events.publish(AuctionEvent::BidPlaced {
+            auction_id,
+            auction: auction.clone(),
+            bid: highest,
+        });
+        if let Some(outbid) = outbid {
+            self.release_funds(&outbid.bidder, outbid.amount).await?;
+            self.events.publish(AuctionEvent::Outbid {
+                auction_id,
+                bid: outbid,
+            });
+        }
+        let price = auction.current_price();
+        if extended {
+            self.events.publish(AuctionEvent::Extended {
+                auction_id,
+                auction,
+            });
+        }
+        if outbid_at_once {
+            return Err(Status::new(
+                tonic::Code::FailedPrecondition,
+                format!(
+                    "Bid has been outbid by a higher maximum bid, the current price is {}",
+                    price
+                ),
+            ));
+        }
+        Ok(Response::new(()))
+    }
+
+    async fn buy_now(&self, request: Request<BuyNowRequest>) -> Result<Response<()>, Status> {
+        let user = self.get_or_add_user(&request).await?;
+        let auction_id = parse_auction_id(&request.into_inner().auction_id)?;
+        // the buy now price never changes, so it can be held before the item is bought
+        let price = self
+            .auctions
+            .get_auction(auction_id)
+            .await
+            .map_err(storage_error_to_status)?
+            .buy_now_price()
+            .ok_or_else(|| {
+                Status::new(
+                    tonic::Code::FailedPrecondition,
+                    "Buy now is not offered".to_string(),
+                )
+            })?;
+        self.users
+            .hold_funds(&user, price)
+            .await
+            .map_err(storage_error_to_status)?;
+        let PlacedBid {
+            auction, outbid, ..
+        } = match self.auctions.buy_now(auction_id, &user).await {
+            Ok(bought) => bought,
+            Err(error) => {
+                self.release_funds(&user, price).await?;
+                return Err(storage_error_to_status(error));
+            }
+        };
+        if let Some(outbid) = outbid {
+            self.release_funds(&outbid.bidder, outbid.amount).await?;
+            self.events.publish(AuctionEvent::Outbid {
+                auction_id,
+                bid: outbid,
+            });
+        }
+        self.events.publish(AuctionEvent::Bought {
+            auction_id,
+            auction: auction.clone(),
+            bid: Bid {
+                bidder: user,
+                amount: price,
+            },
+        });
+        let outcome = self.settlement().settle(auction_id, &auction).await;
+        // the settlement is complete, if the auction cannot be removed, it is settled again
+        // without any effect when the scheduler resumes interrupted settlements
+        if let Err(error) = self.auctions.remove_settled_auction(auction_id).await {
+            eprintln!("Failed to remove settled auction {}: {}", auction_id, error);
+        }
+        self.events.publish(AuctionEvent::Settled {
+            auction_id,
+            auction,
+            outcome: outcome.clone(),
+        });
+        match outcome {
+            Outcome::Sold(_) => Ok(Response::new(())),
+            // the purchase has been refunded and the item returned to the seller
+            Outcome::Failed(reasons) => Err(storage_error_to_status(reasons.into())),
+            outcome => Err(Status::new(
+                tonic::Code::Internal,
+                format!("Unexpected outcome of buy now: {:?}", outcome),
+            )),
+        }
+    }
+
+    async fn close_auction(
+        &self,
+        request: Request<CloseAuctionRequest>,
+    ) -> Result<Response<()>, Status> {
+        let user = get_user(&request)?;
+        let auction_id = parse_auction_id(&request.into_inner().auction_id)?;
+        let auction = self
+            .auctions
+            .get_auction(auction_id)
+            .await
+            .map_err(storage_error_to_status)?;
+        if auction.seller() != user {
+            return Err(Status::new(
+                tonic::Code::PermissionDenied,
+                "Only the seller can close the auction".to_string(),
+            ));
+        }
+        // the item is returned first, so it is never lost with the removed auction
+        self.return_item(&user, auction.item()).await?;
+        let auction = match self.auctions.close_auction(auction_id, &user).await {
+            Ok(auction) => auction,
+            Err(error) => {
+                self.users
+                    .withdraw_item(&user, auction.item())
+                    .await
+                    .map_err(storage_error_to_status)?;
+                return Err(storage_error_to_status(error));
+            }
+        };
+        for held in auction.held_bids() {
+            self.release_funds(&held.bidder, held.amount).await?;
+        }
+        self.events.publish(AuctionEvent::Closed {
+            auction_id,
+            auction,
+        });
+        Ok(Response::new(()))
     }
 
     async fn list_auctions(
         &self,
-        request: Request<()>,
+        _request: Request<()>,
     ) -> Result<Response<ListAuctionsResponse>, Status> {
-        unimplemented!()
+        let auctions = list_ongoing_auctions(self.auctions.as_ref()).await?;
+        Ok(Response::new(ListAuctionsResponse { auctions }))
     }
 
     type WatchAuctionsStream = ResponseStream<ListAuctionsResponse>;
     async fn watch_auctions(
         &self,
-        request: Request<()>,
+        _request: Request<()>,
     ) -> Result<Response<Self::WatchAuctionsStream>, Status> {
-        unimplemented!()
+        // subscribed before listing the auctions, so no change is missed
+        let events = self.events.subscribe();
+        let auctions = list_ongoing_auctions(self.auctions.as_ref()).await?;
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        sender
+            .send(Ok(ListAuctionsResponse { auctions }))
+            .await
+            .map_err(|_| Status::new(tonic::Code::Internal, "Stream has been closed"))?;
+        tokio::spawn(send_auctions(self.auctions.clone(), events, sender));
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    type WatchAuctionChangesStream = ResponseStream<AuctionChange>;
+
+    async fn watch_auction_changes(
+        &self,
+        request: Request<WatchAuctionChangesRequest>,
+    ) -> Result<Response<Self::WatchAuctionChangesStream>, Status> {
+        let last_sequence = request.into_inner().last_sequence;
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(send_auction_changes(
+            self.auctions.clone(),
+            self.events.clone(),
+            last_sequence,
+            sender,
+        ));
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
     }
 
     type WatchUserAuctionsStream = ResponseStream<WatchUserAuctionsResponse>;
@@ -121,6 +1039,1554 @@ where
         &self,
         request: Request<()>,
     ) -> Result<Response<Self::WatchUserAuctionsStream>, Status> {
-        unimplemented!()
+        let user = get_user(&request)?;
+        let events = self.events.subscribe();
+        // the user's bids are taken from the storage, so sealed and outbid bids are included
+        let watched: HashSet<_> = self
+            .auctions
+            .list_ongoing_auctions()
+            .await
+            .map_err(storage_error_to_status)?
+            .iter()
+            .filter(|(_, auction)| auction.involves(&user))
+            .map(|(auction_id, _)| auction_id.to_string())
+            .collect();
+        let auctions: Vec<_> = list_ongoing_auctions(self.auctions.as_ref())
+            .await?
+            .into_iter()
+            .filter(|auction| watched.contains(&auction.id))
+            .collect();
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        sender
+            .send(Ok(WatchUserAuctionsResponse {
+                auctions,
+                ..Default::default()
+            }))
+            .await
+            .map_err(|_| Status::new(tonic::Code::Internal, "Stream has been closed"))?;
+        tokio::spawn(send_user_auctions(
+            self.auctions.clone(),
+            user,
+            watched,
+            events,
+            sender,
+        ));
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    const TIMEOUT: Duration = Duration::from_secs(3);
+
+    /// Returns the next response of the stream, failing if it does not come in time.
+    async fn next<T>(stream: &mut ResponseStream<T>) -> T {
+        tokio::time::timeout(TIMEOUT, stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap()
+    }
+
+    fn authorized_request<T>(message: T, user: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .extensions_mut()
+            .insert(AuthenticatedUser(user.to_owned()));
+        request
+    }
+
+    async fn deposit_funds(service: &DefaultBackendService, user: &str, amount: u64) {
+        let request = authorized_request(DepositFundsRequest { amount }, user);
+        service.deposit_funds(request).await.unwrap();
+    }
+
+    async fn show_funds(service: &DefaultBackendService, user: &str) -> ShowFundsResponse {
+        service
+            .show_funds(authorized_request((), user))
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    async fn deposit_item(service: &DefaultBackendService, user: &str, item: &str) {
+        let request = authorized_request(DepositItemRequest { item: item.into() }, user);
+        service.deposit_item(request).await.unwrap();
+    }
+
+    async fn show_items(service: &DefaultBackendService, user: &str) -> Vec<String> {
+        service
+            .show_items(authorized_request((), user))
+            .await
+            .unwrap()
+            .into_inner()
+            .items
+    }
+
+    /// Puts an item up for sale and returns the auction's id.
+    async fn sell_item(service: &DefaultBackendService, seller: &str) -> String {
+        deposit_item(service, seller, "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                ..Default::default()
+            },
+            seller,
+        );
+        service.sell_item(request).await.unwrap().into_inner().id
+    }
+
+    async fn bid_item(
+        service: &DefaultBackendService,
+        auction_id: &str,
+        bidder: &str,
+        price: u64,
+    ) -> Result<Response<()>, Status> {
+        let request = authorized_request(
+            BidItemRequest {
+                auction_id: auction_id.to_owned(),
+                price,
+                proxy: false,
+            },
+            bidder,
+        );
+        service.bid_item(request).await
+    }
+
+    #[tokio::test]
+    async fn test_deposit_and_show_funds() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(DepositFundsRequest { amount: 100 }, "user");
+        service.deposit_funds(request).await.unwrap();
+        let response = service
+            .show_funds(authorized_request((), "user"))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().funds, 100);
+    }
+
+    #[tokio::test]
+    async fn test_try_deposit_funds_without_authentication() {
+        let service = DefaultBackendService::default();
+        let request = Request::new(DepositFundsRequest { amount: 100 });
+        let status = service.deposit_funds(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_try_deposit_funds_exceeding_max() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(
+            DepositFundsRequest {
+                amount: u64::from(Funds::MAX) + 1,
+            },
+            "user",
+        );
+        let status = service.deposit_funds(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_try_withdraw_insufficient_funds() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(WithdrawFundsRequest { amount: 100 }, "user");
+        let status = service.withdraw_funds(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_and_withdraw_items() {
+        let service = DefaultBackendService::default();
+        for item in ["item2", "item1"] {
+            let request = authorized_request(DepositItemRequest { item: item.into() }, "user");
+            service.deposit_item(request).await.unwrap();
+        }
+        let request = authorized_request(
+            WithdrawItemRequest {
+                item: "item2".into(),
+            },
+            "user",
+        );
+        service.withdraw_item(request).await.unwrap();
+        let response = service
+            .show_items(authorized_request((), "user"))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().items, vec!["item1".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_try_deposit_item_twice() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(
+            DepositItemRequest {
+                item: "item".into(),
+            },
+            "user",
+        );
+        service.deposit_item(request).await.unwrap();
+        let request = authorized_request(
+            DepositItemRequest {
+                item: "item".into(),
+            },
+            "user",
+        );
+        let status = service.deposit_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn test_try_withdraw_item_that_does_not_exist() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(
+            WithdrawItemRequest {
+                item: "item".into(),
+            },
+            "user",
+        );
+        let status = service.withdraw_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_sell_bid_and_list_auctions() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                ..Default::default()
+            },
+            "seller",
+        );
+        let created = service.sell_item(request).await.unwrap().into_inner();
+        assert_eq!(created.item, "item");
+        assert_eq!(created.seller, "seller");
+        assert!(created.id.parse::<AuctionId>().is_ok());
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions, vec![created]);
+        assert_eq!(auctions[0].item, "item");
+        assert_eq!(auctions[0].price, 10);
+        assert_eq!(auctions[0].seller, "seller");
+        assert_eq!(auctions[0].buyer, "");
+        assert_eq!(auctions[0].ends_at - auctions[0].created_at, 100);
+
+        deposit_funds(&service, "bidder", 20).await;
+        let request = authorized_request(
+            BidItemRequest {
+                auction_id: auctions[0].id.clone(),
+                price: 20,
+                proxy: false,
+            },
+            "bidder",
+        );
+        service.bid_item(request).await.unwrap();
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].price, 20);
+        assert_eq!(auctions[0].buyer, "bidder");
+    }
+
+    #[tokio::test]
+    async fn test_try_bid_own_auction() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                ..Default::default()
+            },
+            "seller",
+        );
+        service.sell_item(request).await.unwrap();
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        deposit_funds(&service, "seller", 20).await;
+        let request = authorized_request(
+            BidItemRequest {
+                auction_id: auctions[0].id.clone(),
+                price: 20,
+                proxy: false,
+            },
+            "seller",
+        );
+        let status = service.bid_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_try_bid_below_starting_price() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder", 20).await;
+        let status = bid_item(&service, &auction_id, "bidder", 9)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "Bid amount is lower than the minimum bid of 10"
+        );
+        assert_eq!(show_funds(&service, "bidder").await.held_funds, 0);
+        bid_item(&service, &auction_id, "bidder", 10).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_try_bid_below_min_increment() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 100,
+                duration: 100,
+                min_increment: Some(sell_item_request::MinIncrement::MinIncrementPercent(5)),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let auction_id = service.sell_item(request).await.unwrap().into_inner().id;
+        deposit_funds(&service, "bidder1", 200).await;
+        deposit_funds(&service, "bidder2", 200).await;
+        bid_item(&service, &auction_id, "bidder1", 100)
+            .await
+            .unwrap();
+        let status = bid_item(&service, &auction_id, "bidder2", 104)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            status.message(),
+            "Bid amount is lower than the minimum bid of 105"
+        );
+        bid_item(&service, &auction_id, "bidder2", 105)
+            .await
+            .unwrap();
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].min_bid, 111);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_with_zero_min_increment() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                min_increment: Some(sell_item_request::MinIncrement::MinIncrementAmount(0)),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let status = service.sell_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_with_too_long_duration() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: u64::MAX,
+                ..Default::default()
+            },
+            "seller",
+        );
+        let status = service.sell_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_with_too_long_soft_close() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let soft_closes = [
+            backend_proto::SoftClose {
+                window: u64::MAX,
+                extension: 60,
+                max_extension: None,
+            },
+            backend_proto::SoftClose {
+                window: 60,
+                extension: u64::MAX,
+                max_extension: None,
+            },
+            backend_proto::SoftClose {
+                window: 60,
+                extension: 60,
+                max_extension: Some(u64::MAX),
+            },
+        ];
+        for soft_close in soft_closes {
+            let request = authorized_request(
+                SellItemRequest {
+                    item: "item".into(),
+                    price: 10,
+                    duration: 100,
+                    soft_close: Some(soft_close),
+                    ..Default::default()
+                },
+                "seller",
+            );
+            let status = service.sell_item(request).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        }
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+    }
+
+    /// Puts an item up for sale with a buy now price of 50 and returns the auction's id.
+    async fn sell_item_with_buy_now_price(service: &DefaultBackendService, seller: &str) -> String {
+        deposit_item(service, seller, "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                buy_now_price: Some(50),
+                ..Default::default()
+            },
+            seller,
+        );
+        service.sell_item(request).await.unwrap().into_inner().id
+    }
+
+    async fn buy_now(
+        service: &DefaultBackendService,
+        auction_id: &str,
+        buyer: &str,
+    ) -> Result<Response<()>, Status> {
+        let request = authorized_request(
+            BuyNowRequest {
+                auction_id: auction_id.to_owned(),
+            },
+            buyer,
+        );
+        service.buy_now(request).await
+    }
+
+    #[tokio::test]
+    async fn test_buy_now() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item_with_buy_now_price(&service, "seller").await;
+        deposit_funds(&service, "bidder", 100).await;
+        deposit_funds(&service, "buyer", 100).await;
+        bid_item(&service, &auction_id, "bidder", 20).await.unwrap();
+        buy_now(&service, &auction_id, "buyer").await.unwrap();
+        assert_eq!(show_funds(&service, "seller").await.funds, 50);
+        assert_eq!(show_items(&service, "buyer").await, vec!["item"]);
+        let funds = show_funds(&service, "buyer").await;
+        assert_eq!(funds.funds, 50);
+        assert_eq!(funds.held_funds, 0);
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert!(auctions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_try_buy_now_with_insufficient_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item_with_buy_now_price(&service, "seller").await;
+        deposit_funds(&service, "buyer", 49).await;
+        let status = buy_now(&service, &auction_id, "buyer").await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].buy_now_price, Some(50));
+    }
+
+    #[tokio::test]
+    async fn test_try_buy_now_after_bid_exceeds_it() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item_with_buy_now_price(&service, "seller").await;
+        deposit_funds(&service, "bidder", 100).await;
+        deposit_funds(&service, "buyer", 100).await;
+        bid_item(&service, &auction_id, "bidder", 51).await.unwrap();
+        let status = buy_now(&service, &auction_id, "buyer").await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(show_funds(&service, "buyer").await.funds, 100);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].buy_now_price, None);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_with_buy_now_price_below_starting_price() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                buy_now_price: Some(9),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let status = service.sell_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    async fn proxy_bid_item(
+        service: &DefaultBackendService,
+        auction_id: &str,
+        bidder: &str,
+        max_price: u64,
+    ) -> Result<Response<()>, Status> {
+        let request = authorized_request(
+            BidItemRequest {
+                auction_id: auction_id.to_owned(),
+                price: max_price,
+                proxy: true,
+            },
+            bidder,
+        );
+        service.bid_item(request).await
+    }
+
+    #[tokio::test]
+    async fn test_proxy_bid_holds_its_maximum() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder1", 100).await;
+        deposit_funds(&service, "bidder2", 100).await;
+        proxy_bid_item(&service, &auction_id, "bidder1", 60)
+            .await
+            .unwrap();
+        let funds = show_funds(&service, "bidder1").await;
+        assert_eq!(funds.funds, 40);
+        assert_eq!(funds.held_funds, 60);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].price, 10);
+        assert_eq!(auctions[0].buyer, "bidder1");
+
+        let status = bid_item(&service, &auction_id, "bidder2", 30)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            status.message(),
+            "Bid has been outbid by a higher maximum bid, the current price is 31"
+        );
+        assert_eq!(show_funds(&service, "bidder2").await.funds, 100);
+
+        proxy_bid_item(&service, &auction_id, "bidder2", 70)
+            .await
+            .unwrap();
+        let funds = show_funds(&service, "bidder1").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+        assert_eq!(show_funds(&service, "bidder2").await.held_funds, 70);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].price, 61);
+        assert_eq!(auctions[0].buyer, "bidder2");
+    }
+
+    #[tokio::test]
+    async fn test_close_auction_releases_proxy_bid_maximum() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder", 100).await;
+        proxy_bid_item(&service, &auction_id, "bidder", 60)
+            .await
+            .unwrap();
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_try_bid_with_invalid_auction_id() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(
+            BidItemRequest {
+                auction_id: "not an id".into(),
+                price: 20,
+                proxy: false,
+            },
+            "bidder",
+        );
+        let status = service.bid_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_try_bid_auction_that_does_not_exist() {
+        let service = DefaultBackendService::default();
+        deposit_funds(&service, "bidder", 20).await;
+        let request = authorized_request(
+            BidItemRequest {
+                auction_id: AuctionId::new().to_string(),
+                price: 20,
+                proxy: false,
+            },
+            "bidder",
+        );
+        let status = service.bid_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_bid_holds_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 30).await.unwrap();
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 70);
+        assert_eq!(funds.held_funds, 30);
+        let request = authorized_request(WithdrawFundsRequest { amount: 100 }, "bidder");
+        let status = service.withdraw_funds(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_try_bid_with_insufficient_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder", 10).await;
+        let status = bid_item(&service, &auction_id, "bidder", 20)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 10);
+        assert_eq!(funds.held_funds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_bid_releases_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder1", 100).await;
+        deposit_funds(&service, "bidder2", 100).await;
+        bid_item(&service, &auction_id, "bidder1", 30)
+            .await
+            .unwrap();
+        let status = bid_item(&service, &auction_id, "bidder2", 30)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let funds = show_funds(&service, "bidder2").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_outbid_releases_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder1", 100).await;
+        deposit_funds(&service, "bidder2", 100).await;
+        bid_item(&service, &auction_id, "bidder1", 30)
+            .await
+            .unwrap();
+        bid_item(&service, &auction_id, "bidder2", 40)
+            .await
+            .unwrap();
+        let funds = show_funds(&service, "bidder1").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+        let funds = show_funds(&service, "bidder2").await;
+        assert_eq!(funds.funds, 60);
+        assert_eq!(funds.held_funds, 40);
+    }
+
+    #[tokio::test]
+    async fn test_close_auction_releases_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 30).await.unwrap();
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert!(auctions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_try_close_auction_of_other_user() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        let request = authorized_request(CloseAuctionRequest { auction_id }, "other");
+        let status = service.close_auction(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_sell_item_moves_it_out_of_inventory() {
+        let service = DefaultBackendService::default();
+        sell_item(&service, "seller").await;
+        assert!(show_items(&service, "seller").await.is_empty());
+        let request = authorized_request(
+            WithdrawItemRequest {
+                item: "item".into(),
+            },
+            "seller",
+        );
+        let status = service.withdraw_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_that_does_not_exist() {
+        let service = DefaultBackendService::default();
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                ..Default::default()
+            },
+            "seller",
+        );
+        let status = service.sell_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_twice() {
+        let service = DefaultBackendService::default();
+        sell_item(&service, "seller").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                ..Default::default()
+            },
+            "seller",
+        );
+        let status = service.sell_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_close_auction_returns_item() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        let request = authorized_request(CloseAuctionRequest { auction_id }, "seller");
+        service.close_auction(request).await.unwrap();
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+    }
+
+    #[tokio::test]
+    async fn test_try_close_auction_when_item_cannot_be_returned() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        // the seller's inventory already holds an item with the same name
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        let status = service.close_auction(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].id, auction_id);
+    }
+
+    #[tokio::test]
+    async fn test_try_close_concluded_auction_keeps_item_out_of_inventory() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 1,
+                ..Default::default()
+            },
+            "seller",
+        );
+        let auction_id = service.sell_item(request).await.unwrap().into_inner().id;
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let request = authorized_request(CloseAuctionRequest { auction_id }, "seller");
+        let status = service.close_auction(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert!(show_items(&service, "seller").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_auctions() {
+        let service = DefaultBackendService::default();
+        let mut stream = service
+            .watch_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(next(&mut stream).await.auctions.is_empty());
+        let auction_id = sell_item(&service, "seller").await;
+        let auctions = next(&mut stream).await.auctions;
+        assert_eq!(auctions.len(), 1);
+        assert_eq!(auctions[0].id, auction_id);
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 20).await.unwrap();
+        let auctions = next(&mut stream).await.auctions;
+        assert_eq!(auctions[0].buyer, "bidder");
+        assert_eq!(auctions[0].price, 20);
+        let request = authorized_request(CloseAuctionRequest { auction_id }, "seller");
+        service.close_auction(request).await.unwrap();
+        assert!(next(&mut stream).await.auctions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions() {
+        let service = DefaultBackendService::default();
+        let mut seller = service
+            .watch_user_auctions(authorized_request((), "seller"))
+            .await
+            .unwrap()
+            .into_inner();
+        let mut bidder = service
+            .watch_user_auctions(authorized_request((), "bidder"))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(next(&mut seller).await.auctions.is_empty());
+        assert!(next(&mut bidder).await.auctions.is_empty());
+        let auction_id = sell_item(&service, "seller").await;
+        assert_eq!(next(&mut seller).await.auctions[0].id, auction_id);
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 20).await.unwrap();
+        assert_eq!(next(&mut seller).await.auctions[0].buyer, "bidder");
+        assert_eq!(next(&mut bidder).await.auctions[0].id, auction_id);
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        for stream in [&mut seller, &mut bidder] {
+            let response = next(stream).await;
+            assert!(response.auctions.is_empty());
+            assert_eq!(response.finalized_auctions, vec![auction_id.clone()]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions_of_bidder_outbid_at_once() {
+        let service = DefaultBackendService::default();
+        let mut bidder = service
+            .watch_user_auctions(authorized_request((), "bidder"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut bidder).await;
+        let auction_id = sell_item(&service, "seller").await;
+        deposit_funds(&service, "proxy", 100).await;
+        deposit_funds(&service, "bidder", 100).await;
+        proxy_bid_item(&service, &auction_id, "proxy", 50)
+            .await
+            .unwrap();
+        assert!(bid_item(&service, &auction_id, "bidder", 20).await.is_err());
+        let response = next(&mut bidder).await;
+        assert_eq!(response.auctions[0].id, auction_id);
+        assert_eq!(response.auctions[0].buyer, "proxy");
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        assert_eq!(next(&mut bidder).await.finalized_auctions, vec![auction_id]);
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions_includes_earlier_sealed_bids() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                auction_type: backend_proto::AuctionType::SealedFirstPrice.into(),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let auction_id = service.sell_item(request).await.unwrap().into_inner().id;
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 20).await.unwrap();
+        let mut bidder = service
+            .watch_user_auctions(authorized_request((), "bidder"))
+            .await
+            .unwrap()
+            .into_inner();
+        let response = next(&mut bidder).await;
+        assert_eq!(response.auctions.len(), 1);
+        assert_eq!(response.auctions[0].id, auction_id);
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        assert_eq!(next(&mut bidder).await.finalized_auctions, vec![auction_id]);
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions_skips_other_users_auctions() {
+        let service = DefaultBackendService::default();
+        let mut stream = service
+            .watch_user_auctions(authorized_request((), "user"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut stream).await;
+        let other_auction_id = sell_item(&service, "other").await;
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: other_auction_id,
+            },
+            "other",
+        );
+        service.close_auction(request).await.unwrap();
+        let auction_id = sell_item(&service, "user").await;
+        let response = next(&mut stream).await;
+        assert_eq!(response.auctions.len(), 1);
+        assert_eq!(response.auctions[0].id, auction_id);
+        assert!(response.finalized_auctions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions_reports_expired_and_settled_auctions() {
+        let service = DefaultBackendService::default();
+        let scheduler = Scheduler::spawn(service.settlement(), service.events())
+            .await
+            .unwrap();
+        let service = service.with_scheduler(scheduler);
+        let mut stream = service
+            .watch_user_auctions(authorized_request((), "seller"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut stream).await;
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 1,
+                ..Default::default()
+            },
+            "seller",
+        );
+        service.sell_item(request).await.unwrap();
+        let auction_id = next(&mut stream).await.auctions[0].id.clone();
+        let response = next(&mut stream).await;
+        assert!(response.auctions.is_empty());
+        assert_eq!(response.expired_auctions, vec![auction_id.clone()]);
+        let response = next(&mut stream).await;
+        assert_eq!(response.finalized_auctions, vec![auction_id]);
+        assert!(response.reserve_not_met_auctions.is_empty());
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions_reports_reserve_not_met() {
+        let service = DefaultBackendService::default();
+        let scheduler = Scheduler::spawn(service.settlement(), service.events())
+            .await
+            .unwrap();
+        let service = service.with_scheduler(scheduler);
+        let mut stream = service
+            .watch_user_auctions(authorized_request((), "seller"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut stream).await;
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 1,
+                reserve_price: Some(50),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let auction_id = service.sell_item(request).await.unwrap().into_inner().id;
+        assert_eq!(next(&mut stream).await.auctions[0].reserve_met, Some(false));
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 20).await.unwrap();
+        assert_eq!(next(&mut stream).await.auctions[0].reserve_met, Some(false));
+        let response = next(&mut stream).await;
+        assert_eq!(response.expired_auctions, vec![auction_id.clone()]);
+        let response = next(&mut stream).await;
+        assert_eq!(response.finalized_auctions, vec![auction_id.clone()]);
+        assert_eq!(response.reserve_not_met_auctions, vec![auction_id]);
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_watch_user_auctions_reports_extended_auctions() {
+        let service = DefaultBackendService::default();
+        let scheduler = Scheduler::spawn(service.settlement(), service.events())
+            .await
+            .unwrap();
+        let service = service.with_scheduler(scheduler);
+        let mut stream = service
+            .watch_user_auctions(authorized_request((), "seller"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut stream).await;
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 1,
+                soft_close: Some(backend_proto::SoftClose {
+                    window: 1,
+                    extension: 1,
+                    max_extension: None,
+                }),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let created = service.sell_item(request).await.unwrap().into_inner();
+        next(&mut stream).await;
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &created.id, "bidder", 10).await.unwrap();
+        let response = next(&mut stream).await;
+        assert_eq!(response.auctions[0].ends_at, created.ends_at + 1);
+        let response = next(&mut stream).await;
+        assert_eq!(response.extended_auctions, vec![created.id.clone()]);
+        // the auction is settled at the extended end time
+        let response = next(&mut stream).await;
+        assert_eq!(response.expired_auctions, vec![created.id.clone()]);
+        assert!(to_timestamp(SystemTime::now()) > created.ends_at);
+        let response = next(&mut stream).await;
+        assert_eq!(response.finalized_auctions, vec![created.id]);
+        assert_eq!(show_funds(&service, "seller").await.funds, 10);
+    }
+
+    async fn watch_auction_changes(
+        service: &DefaultBackendService,
+        last_sequence: Option<u64>,
+    ) -> ResponseStream<AuctionChange> {
+        let request = Request::new(WatchAuctionChangesRequest { last_sequence });
+        service
+            .watch_auction_changes(request)
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_first_requests_of_user() {
+        let service = DefaultBackendService::default();
+        let (first, second) = tokio::join!(
+            service.deposit_funds(authorized_request(
+                DepositFundsRequest { amount: 10 },
+                "user"
+            )),
+            service.deposit_funds(authorized_request(
+                DepositFundsRequest { amount: 20 },
+                "user"
+            )),
+        );
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(show_funds(&service, "user").await.funds, 30);
+    }
+
+    #[tokio::test]
+    async fn test_latest_update_of_concurrent_bids_has_highest_version() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item(&service, "seller").await;
+        let mut stream = watch_auction_changes(&service, None).await;
+        next(&mut stream).await;
+        deposit_funds(&service, "bidder1", 100).await;
+        deposit_funds(&service, "bidder2", 100).await;
+        let (first, second) = tokio::join!(
+            bid_item(&service, &auction_id, "bidder1", 20),
+            bid_item(&service, &auction_id, "bidder2", 30),
+        );
+        assert!(first.is_ok() || second.is_ok());
+        let mut updates = Vec::new();
+        while updates.len() < usize::from(first.is_ok()) + usize::from(second.is_ok()) {
+            if let Some(auction_change::Change::Updated(auction)) = next(&mut stream).await.change {
+                updates.push(auction);
+            }
+        }
+        let latest = updates
+            .into_iter()
+            .max_by_key(|auction| auction.version)
+            .unwrap();
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0], latest);
+    }
+
+    #[tokio::test]
+    async fn test_watch_auction_changes() {
+        let service = DefaultBackendService::default();
+        let mut stream = watch_auction_changes(&service, None).await;
+        let snapshot = next(&mut stream).await;
+        assert_eq!(
+            snapshot.change,
+            Some(auction_change::Change::Snapshot(ListAuctionsResponse {
+                auctions: vec![]
+            }))
+        );
+        let auction_id = sell_item(&service, "seller").await;
+        let created = next(&mut stream).await;
+        assert!(created.sequence > snapshot.sequence);
+        assert!(
+            matches!(created.change, Some(auction_change::Change::Created(auction)) if auction.id == auction_id)
+        );
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 20).await.unwrap();
+        let updated = next(&mut stream).await;
+        assert!(updated.sequence > created.sequence);
+        assert!(
+            matches!(updated.change, Some(auction_change::Change::Updated(auction)) if auction.price == 20 && auction.buyer == "bidder")
+        );
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        let removed = next(&mut stream).await;
+        assert!(removed.sequence > updated.sequence);
+        assert_eq!(
+            removed.change,
+            Some(auction_change::Change::Removed(auction_id))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_auction_changes() {
+        let service = DefaultBackendService::default();
+        let mut stream = watch_auction_changes(&service, None).await;
+        next(&mut stream).await;
+        let first_auction_id = sell_item(&service, "seller1").await;
+        let last_sequence = next(&mut stream).await.sequence;
+        drop(stream);
+        let second_auction_id = sell_item(&service, "seller2").await;
+        let mut stream = watch_auction_changes(&service, Some(last_sequence)).await;
+        let created = next(&mut stream).await;
+        assert!(created.sequence > last_sequence);
+        assert!(
+            matches!(created.change, Some(auction_change::Change::Created(auction)) if auction.id == second_auction_id)
+        );
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: first_auction_id.clone(),
+            },
+            "seller1",
+        );
+        service.close_auction(request).await.unwrap();
+        assert_eq!(
+            next(&mut stream).await.change,
+            Some(auction_change::Change::Removed(first_auction_id))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_auction_changes_from_unknown_sequence() {
+        let service = DefaultBackendService::default();
+        sell_item(&service, "seller").await;
+        let mut stream = watch_auction_changes(&service, Some(1)).await;
+        assert!(matches!(
+            next(&mut stream).await.change,
+            Some(auction_change::Change::Snapshot(snapshot)) if snapshot.auctions.len() == 1
+        ));
+    }
+
+    /// Puts an item up for sale in a Dutch auction whose price drops from 100 to 40
+    /// by 10 every 1000 seconds and returns the auction's id.
+    async fn sell_item_in_dutch_auction(service: &DefaultBackendService, seller: &str) -> String {
+        deposit_item(service, seller, "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 100,
+                duration: 100,
+                auction_type: backend_proto::AuctionType::Dutch.into(),
+                price_schedule: Some(backend_proto::PriceSchedule {
+                    floor_price: 40,
+                    step: Some(backend_proto::PriceStep {
+                        amount: 10,
+                        interval: 1000,
+                    }),
+                }),
+                ..Default::default()
+            },
+            seller,
+        );
+        service.sell_item(request).await.unwrap().into_inner().id
+    }
+
+    #[tokio::test]
+    async fn test_dutch_auction_is_won_by_first_bid() {
+        let service = DefaultBackendService::default();
+        let scheduler = Scheduler::spawn(service.settlement(), service.events())
+            .await
+            .unwrap();
+        let service = service.with_scheduler(scheduler);
+        let mut stream = service
+            .watch_user_auctions(authorized_request((), "seller"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut stream).await;
+        let auction_id = sell_item_in_dutch_auction(&service, "seller").await;
+        let auction = next(&mut stream).await.auctions.remove(0);
+        assert_eq!(
+            auction.auction_type,
+            backend_proto::AuctionType::Dutch as i32
+        );
+        assert_eq!(auction.price, 100);
+        deposit_funds(&service, "bidder", 200).await;
+        deposit_funds(&service, "other", 200).await;
+        let status = bid_item(&service, &auction_id, "bidder", 99)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        bid_item(&service, &auction_id, "bidder", 150)
+            .await
+            .unwrap();
+        let status = bid_item(&service, &auction_id, "other", 150)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        loop {
+            if next(&mut stream).await.finalized_auctions == vec![auction_id.clone()] {
+                break;
+            }
+        }
+        assert_eq!(show_funds(&service, "seller").await.funds, 100);
+        assert_eq!(show_items(&service, "bidder").await, vec!["item"]);
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+        assert_eq!(show_funds(&service, "other").await.funds, 200);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert!(auctions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_in_dutch_auction_with_invalid_schedule() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let requests = [
+            SellItemRequest {
+                auction_type: backend_proto::AuctionType::Dutch.into(),
+                ..Default::default()
+            },
+            SellItemRequest {
+                auction_type: backend_proto::AuctionType::Dutch.into(),
+                price_schedule: Some(backend_proto::PriceSchedule {
+                    floor_price: 11,
+                    step: None,
+                }),
+                ..Default::default()
+            },
+            SellItemRequest {
+                auction_type: backend_proto::AuctionType::Dutch.into(),
+                price_schedule: Some(backend_proto::PriceSchedule {
+                    floor_price: 5,
+                    step: Some(backend_proto::PriceStep {
+                        amount: 1,
+                        interval: 0,
+                    }),
+                }),
+                ..Default::default()
+            },
+            SellItemRequest {
+                auction_type: backend_proto::AuctionType::Dutch.into(),
+                price_schedule: Some(backend_proto::PriceSchedule {
+                    floor_price: 5,
+                    step: None,
+                }),
+                reserve_price: Some(8),
+                ..Default::default()
+            },
+        ];
+        for request in requests {
+            let request = SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                ..request
+            };
+            let status = service
+                .sell_item(authorized_request(request, "seller"))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        }
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
+    }
+
+    /// Puts an item up for sale in a sealed-bid auction with a starting price of 10 and returns the auction's id.
+    async fn sell_item_in_sealed_bid_auction(
+        service: &DefaultBackendService,
+        seller: &str,
+        auction_type: backend_proto::AuctionType,
+    ) -> String {
+        deposit_item(service, seller, "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 1,
+                auction_type: auction_type.into(),
+                ..Default::default()
+            },
+            seller,
+        );
+        service.sell_item(request).await.unwrap().into_inner().id
+    }
+
+    #[tokio::test]
+    async fn test_sealed_bid_auction_lists_only_bid_count() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item_in_sealed_bid_auction(
+            &service,
+            "seller",
+            backend_proto::AuctionType::SealedFirstPrice,
+        )
+        .await;
+        deposit_funds(&service, "bidder1", 100).await;
+        deposit_funds(&service, "bidder2", 100).await;
+        bid_item(&service, &auction_id, "bidder1", 50)
+            .await
+            .unwrap();
+        bid_item(&service, &auction_id, "bidder2", 20)
+            .await
+            .unwrap();
+        // a revised bid releases the previous one
+        bid_item(&service, &auction_id, "bidder1", 30)
+            .await
+            .unwrap();
+        let funds = show_funds(&service, "bidder1").await;
+        assert_eq!(funds.funds, 70);
+        assert_eq!(funds.held_funds, 30);
+        let auctions = service
+            .list_auctions(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auctions;
+        assert_eq!(auctions[0].bid_count, Some(2));
+        assert_eq!(auctions[0].price, 10);
+        assert_eq!(auctions[0].buyer, "");
+        assert_eq!(
+            auctions[0].auction_type,
+            backend_proto::AuctionType::SealedFirstPrice as i32
+        );
+        let request = authorized_request(
+            CloseAuctionRequest {
+                auction_id: auction_id.clone(),
+            },
+            "seller",
+        );
+        service.close_auction(request).await.unwrap();
+        assert_eq!(show_funds(&service, "bidder1").await.funds, 100);
+        assert_eq!(show_funds(&service, "bidder2").await.funds, 100);
+    }
+
+    #[tokio::test]
+    async fn test_raise_sealed_bid_with_all_funds() {
+        let service = DefaultBackendService::default();
+        let auction_id = sell_item_in_sealed_bid_auction(
+            &service,
+            "seller",
+            backend_proto::AuctionType::SealedFirstPrice,
+        )
+        .await;
+        deposit_funds(&service, "bidder", 100).await;
+        bid_item(&service, &auction_id, "bidder", 90).await.unwrap();
+        // only the difference to the previous bid has to be available
+        bid_item(&service, &auction_id, "bidder", 100)
+            .await
+            .unwrap();
+        let funds = show_funds(&service, "bidder").await;
+        assert_eq!(funds.funds, 0);
+        assert_eq!(funds.held_funds, 100);
+        let status = bid_item(&service, &auction_id, "bidder", 101)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(show_funds(&service, "bidder").await.held_funds, 100);
+    }
+
+    #[tokio::test]
+    async fn test_sealed_second_price_auction_is_settled_at_second_highest_bid() {
+        let service = DefaultBackendService::default();
+        let scheduler = Scheduler::spawn(service.settlement(), service.events())
+            .await
+            .unwrap();
+        let service = service.with_scheduler(scheduler);
+        let mut stream = service
+            .watch_user_auctions(authorized_request((), "seller"))
+            .await
+            .unwrap()
+            .into_inner();
+        next(&mut stream).await;
+        let auction_id = sell_item_in_sealed_bid_auction(
+            &service,
+            "seller",
+            backend_proto::AuctionType::SealedSecondPrice,
+        )
+        .await;
+        deposit_funds(&service, "bidder1", 100).await;
+        deposit_funds(&service, "bidder2", 100).await;
+        bid_item(&service, &auction_id, "bidder1", 40)
+            .await
+            .unwrap();
+        bid_item(&service, &auction_id, "bidder2", 70)
+            .await
+            .unwrap();
+        loop {
+            if next(&mut stream).await.finalized_auctions == vec![auction_id.clone()] {
+                break;
+            }
+        }
+        assert_eq!(show_funds(&service, "seller").await.funds, 40);
+        assert_eq!(show_items(&service, "bidder2").await, vec!["item"]);
+        let funds = show_funds(&service, "bidder2").await;
+        assert_eq!(funds.funds, 60);
+        assert_eq!(funds.held_funds, 0);
+        let funds = show_funds(&service, "bidder1").await;
+        assert_eq!(funds.funds, 100);
+        assert_eq!(funds.held_funds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_try_sell_item_in_sealed_bid_auction_with_buy_now_price() {
+        let service = DefaultBackendService::default();
+        deposit_item(&service, "seller", "item").await;
+        let request = authorized_request(
+            SellItemRequest {
+                item: "item".into(),
+                price: 10,
+                duration: 100,
+                auction_type: backend_proto::AuctionType::SealedSecondPrice.into(),
+                buy_now_price: Some(50),
+                ..Default::default()
+            },
+            "seller",
+        );
+        let status = service.sell_item(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(show_items(&service, "seller").await, vec!["item"]);
     }
 }
@@ -0,0 +1,213 @@
+use crate::backend::{Auction, AuctionId, Bid};
+use crate::settlement::Outcome;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// How many of the latest events are kept for subscribers which have not received them yet.
+const CAPACITY: usize = 1024;
+
+/// Change of an auction published to the watch streams.
+#[derive(Clone, Debug)]
+pub enum AuctionEvent {
+    Created {
+        auction_id: AuctionId,
+        auction: Auction,
+    },
+    /// The bid has become the highest bid of the auction, or it has been sealed.
+    BidPlaced {
+        auction_id: AuctionId,
+        auction: Auction,
+        bid: Bid,
+    },
+    /// The bid is no longer the highest one and its funds have been released.
+    Outbid { auction_id: AuctionId, bid: Bid },
+    /// A late bid has pushed out the auction's end time.
+    Extended {
+        auction_id: AuctionId,
+        auction: Auction,
+    },
+    /// The item has been bought for the buy now price, which has ended the auction.
+    Bought {
+        auction_id: AuctionId,
+        auction: Auction,
+        bid: Bid,
+    },
+    /// The seller has closed the auction before its end time.
+    Closed {
+        auction_id: AuctionId,
+        auction: Auction,
+    },
+    /// The auction has reached its end time and it is no longer ongoing.
+    Expired {
+        auction_id: AuctionId,
+        auction: Auction,
+    },
+    /// Funds and the item of a concluded auction have been transferred.
+    Settled {
+        auction_id: AuctionId,
+        auction: Auction,
+        outcome: Outcome,
+    },
+}
+
+impl AuctionEvent {
+    pub fn auction_id(&self) -> AuctionId {
+        match self {
+            AuctionEvent::Created { auction_id, .. }
+            | AuctionEvent::BidPlaced { auction_id, .. }
+            | AuctionEvent::Outbid { auction_id, .. }
+            | AuctionEvent::Extended { auction_id, .. }
+            | AuctionEvent::Bought { auction_id, .. }
+            | AuctionEvent::Closed { auction_id, .. }
+            | AuctionEvent::Expired { auction_id, .. }
+            | AuctionEvent::Settled { auction_id, .. } => *auction_id,
+        }
+    }
+}
+
+/// An event with its sequence number, the number of each event is greater by one than the previous one.
+#[derive(Clone, Debug)]
+pub struct SequencedEvent {
+    pub sequence: u64,
+    pub event: AuctionEvent,
+}
+
+struct History {
+    last_sequence: u64,
+    events: VecDeque<SequencedEvent>,
+}
+
+/// Broadcasts auction events to all subscribers.
+///
+/// Events are numbered and the latest [`CAPACITY`] of them are kept, so a subscriber which has
+/// received some events before can resume after the last one. A subscriber which falls behind
+/// by more than [`CAPACITY`] events misses the oldest of them.
+#[derive(Clone)]
+pub struct EventBus {
+    history: Arc<Mutex<History>>,
+    sender: broadcast::Sender<SequencedEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        // numbering starts at the current time, so the numbers keep increasing after a restart
+        let last_sequence = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as u64;
+        Self {
+            history: Arc::new(Mutex::new(History {
+                last_sequence,
+                events: VecDeque::with_capacity(CAPACITY),
+            })),
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, event: AuctionEvent) {
+        let mut history = self.history.lock().unwrap();
+        history.last_sequence += 1;
+        let event = SequencedEvent {
+            sequence: history.last_sequence,
+            event,
+        };
+        if history.events.len() == CAPACITY {
+            history.events.pop_front();
+        }
+        history.events.push_back(event.clone());
+        // sending fails only if there are no subscribers
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SequencedEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Subscribes to the events and returns the sequence number of the last event published before.
+    pub fn subscribe_with_sequence(&self) -> (u64, broadcast::Receiver<SequencedEvent>) {
+        let history = self.history.lock().unwrap();
+        (history.last_sequence, self.sender.subscribe())
+    }
+
+    /// Subscribes to the events published after the one with the given sequence number.
+    /// # Returns
+    /// The events published after the given one and the receiver of the next ones or `None`
+    /// if some of the events are no longer kept or the sequence number is unknown.
+    pub fn resume(
+        &self,
+        sequence: u64,
+    ) -> Option<(Vec<SequencedEvent>, broadcast::Receiver<SequencedEvent>)> {
+        let history = self.history.lock().unwrap();
+        let first_kept = history.last_sequence + 1 - history.events.len() as u64;
+        if sequence > history.last_sequence || sequence + 1 < first_kept {
+            return None;
+        }
+        let events = history
+            .events
+            .iter()
+            .filter(|event| event.sequence > sequence)
+            .cloned()
+            .collect();
+        Some((events, self.sender.subscribe()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn created(auction_id: AuctionId) -> AuctionEvent {
+        AuctionEvent::Created {
+            auction_id,
+            auction: Auction::new("item", 0, std::time::Duration::ZERO, "seller"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_published_events_are_numbered() {
+        let events = EventBus::default();
+        let (last_sequence, mut receiver) = events.subscribe_with_sequence();
+        let auction_id = AuctionId::new();
+        events.publish(created(auction_id));
+        events.publish(created(AuctionId::new()));
+        let first = receiver.recv().await.unwrap();
+        let second = receiver.recv().await.unwrap();
+        assert_eq!(first.sequence, last_sequence + 1);
+        assert_eq!(first.event.auction_id(), auction_id);
+        assert_eq!(second.sequence, last_sequence + 2);
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_last_received_event() {
+        let events = EventBus::default();
+        let (last_sequence, _) = events.subscribe_with_sequence();
+        let auction_ids = [AuctionId::new(), AuctionId::new(), AuctionId::new()];
+        events.publish(created(auction_ids[0]));
+        events.publish(created(auction_ids[1]));
+        let (missed, mut receiver) = events.resume(last_sequence + 1).unwrap();
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].event.auction_id(), auction_ids[1]);
+        events.publish(created(auction_ids[2]));
+        assert_eq!(
+            receiver.recv().await.unwrap().event.auction_id(),
+            auction_ids[2]
+        );
+        assert!(events.resume(last_sequence + 3).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_resume_after_events_which_are_not_kept() {
+        let events = EventBus::default();
+        let (last_sequence, _) = events.subscribe_with_sequence();
+        for _ in 0..=CAPACITY {
+            events.publish(created(AuctionId::new()));
+        }
+        assert!(events.resume(last_sequence).is_none());
+        assert!(events.resume(last_sequence + 1).is_some());
+        assert!(events.resume(last_sequence + CAPACITY as u64 + 2).is_none());
+    }
+}
@@ -0,0 +1,342 @@
+use crate::authentication::verifier_proto::token_verifier_client::TokenVerifierClient;
+use crate::authentication::verifier_proto::PublicKey;
+use base64::engine::{general_purpose::URL_SAFE_NO_PAD, Engine};
+use jwt::{AlgorithmType, Header, RegisteredClaims, Token, VerifyWithKey, VerifyingAlgorithm};
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+#[derive(Deserialize)]
+struct Claims {
+    user: String,
+    #[serde(flatten)]
+    registered: RegisteredClaims,
+}
+
+/// ES256 public key published by the session service.
+struct Es256Key(VerifyingKey);
+
+impl Es256Key {
+    fn from_jwk(key: &PublicKey) -> Option<Self> {
+        if key.kty != "EC" || key.alg != "ES256" || key.crv != "P-256" {
+            return None;
+        }
+        let x = URL_SAFE_NO_PAD.decode(&key.x).ok()?;
+        let y = URL_SAFE_NO_PAD.decode(&key.y).ok()?;
+        if x.len() != 32 || y.len() != 32 {
+            return None;
+        }
+        let point = p256::EncodedPoint::from_affine_coordinates(
+            x.as_slice().into(),
+            y.as_slice().into(),
+            false,
+        );
+        VerifyingKey::from_encoded_point(&point).ok().map(Self)
+    }
+}
+
+impl VerifyingAlgorithm for Es256Key {
+    fn algorithm_type(&self) -> AlgorithmType {
+        AlgorithmType::Es256
+    }
+
+    fn verify_bytes(
+        &self,
+        header: &str,
+        claims: &str,
+        signature: &[u8],
+    ) -> Result<bool, jwt::Error> {
+        let Ok(signature) = Signature::from_slice(signature) else {
+            return Ok(false);
+        };
+        let message = format!("{}.{}", header, claims);
+        Ok(self.0.verify(message.as_bytes(), &signature).is_ok())
+    }
+}
+
+/// Result of verifying a token without calling the session service.
+#[derive(Debug)]
+pub enum Verification {
+    /// The token's signature and claims are valid, whether it has been revoked is to be checked
+    /// with the session service.
+    Verified {
+        user: String,
+        token_id: String,
+    },
+    Rejected(Status),
+    /// The token is signed with a key which is not known locally, e.g. a symmetric key
+    /// or a key which has been rotated after the last refresh, or the cached keys are too old to be trusted.
+    UnknownKey,
+}
+
+#[derive(Default)]
+struct State {
+    keys: HashMap<String, Es256Key>,
+    /// When the keys have been fetched.
+    refreshed_at: Option<Instant>,
+}
+
+/// Verifies ES256 tokens with public keys fetched from the session service.
+///
+/// Keys are cached and replaced by [`LocalVerifier::refresh`]. If the cache has not been refreshed
+/// for longer than the maximum staleness, e.g. because the session is unreachable, no token is verified
+/// locally, so keys retired by the session are not trusted for longer than that.
+pub struct LocalVerifier {
+    state: RwLock<State>,
+    max_staleness: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn rejected(message: &str) -> Verification {
+    Verification::Rejected(Status::new(Code::Unauthenticated, message))
+}
+
+impl LocalVerifier {
+    pub fn new(max_staleness: Duration) -> Self {
+        Self {
+            state: RwLock::default(),
+            max_staleness,
+        }
+    }
+
+    pub fn verify(&self, token_str: &str) -> Verification {
+        let unverified: Result<Token<Header, Claims, _>, _> = Token::parse_unverified(token_str);
+        let Ok(unverified) = unverified else {
+            return Verification::Rejected(Status::new(
+                Code::PermissionDenied,
+                "Invalid token - verification has failed",
+            ));
+        };
+        let Some(key_id) = unverified.header().key_id.as_deref() else {
+            return Verification::UnknownKey;
+        };
+        let state = self.state.read().unwrap();
+        let fresh = state
+            .refreshed_at
+            .is_some_and(|refreshed_at| refreshed_at.elapsed() <= self.max_staleness);
+        if !fresh {
+            return Verification::UnknownKey;
+        }
+        let Some(key) = state.keys.get(key_id) else {
+            return Verification::UnknownKey;
+        };
+        let result: Result<Token<Header, Claims, _>, _> = token_str.verify_with_key(key);
+        let Ok(token) = result else {
+            return Verification::Rejected(Status::new(
+                Code::PermissionDenied,
+                "Invalid token - verification has failed",
+            ));
+        };
+        let claims = token.claims();
+        let (Some(expires_at), Some(not_before), Some(token_id)) = (
+            claims.registered.expiration,
+            claims.registered.not_before,
+            claims.registered.json_web_token_id.as_ref(),
+        ) else {
+            return Verification::Rejected(Status::new(
+                Code::PermissionDenied,
+                "Invalid token - registered claims not found",
+            ));
+        };
+        let now = now();
+        if now >= expires_at {
+            return rejected("Token has expired");
+        }
+        if now < not_before {
+            return rejected("Token is not valid yet");
+        }
+        Verification::Verified {
+            user: claims.user.clone(),
+            token_id: token_id.clone(),
+        }
+    }
+
+    /// Replaces cached keys with the current ones from the session service.
+    pub async fn refresh(&self, client: &mut TokenVerifierClient<Channel>) -> Result<(), Status> {
+        let keys = client.get_public_keys(()).await?.into_inner().keys;
+        self.update(&keys);
+        Ok(())
+    }
+
+    fn update(&self, keys: &[PublicKey]) {
+        let keys = keys
+            .iter()
+            .filter_map(|key| Some((key.kid.clone(), Es256Key::from_jwk(key)?)))
+            .collect();
+        *self.state.write().unwrap() = State {
+            keys,
+            refreshed_at: Some(Instant::now()),
+        };
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use argon2::password_hash::rand_core::OsRng;
+    use jwt::{SignWithKey, SigningAlgorithm};
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+    use serde::Serialize;
+
+    pub(crate) const KEY_ID: &str = "key";
+    const MAX_STALENESS: Duration = Duration::from_secs(60);
+
+    struct TestKey(SigningKey);
+
+    impl SigningAlgorithm for TestKey {
+        fn algorithm_type(&self) -> AlgorithmType {
+            AlgorithmType::Es256
+        }
+
+        fn sign(&self, header: &str, claims: &str) -> Result<String, jwt::Error> {
+            let signature: Signature = self.0.sign(format!("{}.{}", header, claims).as_bytes());
+            Ok(URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+        }
+    }
+
+    #[derive(Serialize)]
+    struct TestClaims {
+        user: String,
+        #[serde(flatten)]
+        registered: RegisteredClaims,
+    }
+
+    pub(crate) fn public_key(key: &SigningKey) -> PublicKey {
+        let point = key.verifying_key().to_encoded_point(false);
+        PublicKey {
+            kid: KEY_ID.to_string(),
+            kty: "EC".to_string(),
+            alg: "ES256".to_string(),
+            crv: "P-256".to_string(),
+            x: URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+            y: URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+        }
+    }
+
+    pub(crate) fn sign(key: &SigningKey, key_id: &str, token_id: &str, expires_at: u64) -> String {
+        let header = Header {
+            algorithm: AlgorithmType::Es256,
+            key_id: Some(key_id.to_string()),
+            ..Default::default()
+        };
+        let claims = TestClaims {
+            user: "user".to_string(),
+            registered: RegisteredClaims {
+                expiration: Some(expires_at),
+                not_before: Some(now()),
+                json_web_token_id: Some(token_id.to_string()),
+                ..Default::default()
+            },
+        };
+        Token::new(header, claims)
+            .sign_with_key(&TestKey(key.clone()))
+            .unwrap()
+            .as_str()
+            .to_owned()
+    }
+
+    fn rejection_code(verification: Verification) -> Option<Code> {
+        match verification {
+            Verification::Rejected(status) => Some(status.code()),
+            _ => None,
+        }
+    }
+
+    fn create_verifier(key: &SigningKey) -> LocalVerifier {
+        let verifier = LocalVerifier::new(MAX_STALENESS);
+        verifier.update(&[public_key(key)]);
+        verifier
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = create_verifier(&key);
+        let token = sign(&key, KEY_ID, "id", now() + 60);
+        assert!(
+            matches!(verifier.verify(&token), Verification::Verified { user, token_id } if user == "user" && token_id == "id")
+        );
+    }
+
+    #[test]
+    fn test_verify_token_signed_with_other_key() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = create_verifier(&key);
+        let token = sign(&SigningKey::random(&mut OsRng), KEY_ID, "id", now() + 60);
+        assert_eq!(
+            rejection_code(verifier.verify(&token)),
+            Some(Code::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn test_verify_token_signed_with_unknown_key() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = create_verifier(&key);
+        let token = sign(&key, "other key", "id", now() + 60);
+        assert!(matches!(verifier.verify(&token), Verification::UnknownKey));
+    }
+
+    #[test]
+    fn test_verify_expired_token() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = create_verifier(&key);
+        let token = sign(&key, KEY_ID, "id", now() - 1);
+        assert_eq!(
+            rejection_code(verifier.verify(&token)),
+            Some(Code::Unauthenticated)
+        );
+    }
+
+    #[test]
+    fn test_stale_verifier_does_not_verify_tokens() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = LocalVerifier::new(Duration::from_millis(10));
+        verifier.update(&[public_key(&key)]);
+        let token = sign(&key, KEY_ID, "id", now() + 60);
+        assert!(matches!(
+            verifier.verify(&token),
+            Verification::Verified { .. }
+        ));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(matches!(verifier.verify(&token), Verification::UnknownKey));
+        verifier.update(&[public_key(&key)]);
+        assert!(matches!(
+            verifier.verify(&token),
+            Verification::Verified { .. }
+        ));
+    }
+
+    #[test]
+    fn test_verifier_which_has_not_been_refreshed_does_not_verify_tokens() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = LocalVerifier::new(MAX_STALENESS);
+        let token = sign(&key, KEY_ID, "id", now() + 60);
+        assert!(matches!(verifier.verify(&token), Verification::UnknownKey));
+    }
+
+    #[test]
+    fn test_invalid_public_key_is_skipped() {
+        let key = SigningKey::random(&mut OsRng);
+        let verifier = LocalVerifier::new(MAX_STALENESS);
+        let invalid = PublicKey {
+            x: "invalid".to_string(),
+            ..public_key(&key)
+        };
+        verifier.update(&[invalid]);
+        let token = sign(&key, KEY_ID, "id", now() + 60);
+        assert!(matches!(verifier.verify(&token), Verification::UnknownKey));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::backend::{AuctionsBackend, UsersBackend};
+use crate::events::{AuctionEvent, EventBus};
+use crate::settlement::Settlement;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How long the scheduler waits before retrying a failed settlement.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Handle of the task which settles auctions when they conclude.
+///
+/// The task keeps end times of the ongoing auctions ordered, so it sleeps until the earliest one
+/// instead of polling the storage. It stops when all handles are dropped.
+#[derive(Clone)]
+pub struct Scheduler {
+    end_times: mpsc::UnboundedSender<SystemTime>,
+}
+
+impl Scheduler {
+    /// Spawns the scheduler's task, auctions which are already stored are scheduled too
+    /// and the concluded ones are settled right away, together with the interrupted settlements.
+    pub async fn spawn<UBT, ABT>(
+        settlement: Settlement<UBT, ABT>,
+        events: EventBus,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>>
+    where
+        UBT: UsersBackend + 'static,
+        ABT: AuctionsBackend + 'static,
+    {
+        let mut index: BinaryHeap<_> = settlement
+            .ongoing_end_times()
+            .await?
+            .into_iter()
+            .map(Reverse)
+            .collect();
+        index.push(Reverse(SystemTime::now()));
+        let (end_times, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run(settlement, events, receiver, index));
+        Ok(Self { end_times })
+    }
+
+    /// Schedules settlement of an auction which ends at the given time.
+    pub fn schedule(&self, end_time: SystemTime) {
+        // sending fails only if the task has stopped, which happens only when the runtime shuts down
+        let _ = self.end_times.send(end_time);
+    }
+}
+
+fn to_instant(time: SystemTime) -> Instant {
+    Instant::now() + time.duration_since(SystemTime::now()).unwrap_or_default()
+}
+
+async fn sleep_until(time: Option<SystemTime>) {
+    match time {
+        Some(time) => tokio::time::sleep_until(to_instant(time)).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn run<UBT, ABT>(
+    settlement: Settlement<UBT, ABT>,
+    events: EventBus,
+    mut end_times: mpsc::UnboundedReceiver<SystemTime>,
+    mut index: BinaryHeap<Reverse<SystemTime>>,
+) where
+    UBT: UsersBackend,
+    ABT: AuctionsBackend,
+{
+    // settlements are resumed on start and after a failure, which may have left them settling
+    let mut interrupted = true;
+    loop {
+        let next = index.peek().map(|Reverse(end_time)| *end_time);
+        tokio::select! {
+            end_time = end_times.recv() => match end_time {
+                Some(end_time) => index.push(Reverse(end_time)),
+                None => return,
+            },
+            _ = sleep_until(next) => {
+                let now = SystemTime::now();
+                while index.peek().is_some_and(|Reverse(end_time)| *end_time <= now) {
+                    index.pop();
+                }
+                match settle(&settlement, &events, interrupted).await {
+                    Ok(()) => interrupted = false,
+                    Err(error) => {
+                        eprintln!("Failed to settle concluded auctions: {}", error);
+                        interrupted = true;
+                        index.push(Reverse(now + RETRY_DELAY));
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn settle<UBT, ABT>(
+    settlement: &Settlement<UBT, ABT>,
+    events: &EventBus,
+    interrupted: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    UBT: UsersBackend,
+    ABT: AuctionsBackend,
+{
+    let mut settled = Vec::new();
+    if interrupted {
+        settled.extend(settlement.resume_interrupted().await?);
+    }
+    settled.extend(settlement.settle_concluded().await?);
+    for settled in settled {
+        events.publish(AuctionEvent::Expired {
+            auction_id: settled.auction_id,
+            auction: settled.auction.clone(),
+        });
+        events.publish(AuctionEvent::Settled {
+            auction_id: settled.auction_id,
+            auction: settled.auction,
+            outcome: settled.outcome,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::auctions_memory_storage::AuctionsMemoryStorage;
+    use crate::backend::users_memory_storage::UsersMemoryStorage;
+    use crate::backend::Auction;
+    use crate::events::SequencedEvent;
+    use crate::settlement::Outcome;
+    use std::sync::Arc;
+    use tokio::sync::broadcast;
+
+    const DURATION: Duration = Duration::from_millis(50);
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    async fn create_storages() -> (Arc<UsersMemoryStorage>, Arc<AuctionsMemoryStorage>) {
+        let users = Arc::new(UsersMemoryStorage::default());
+        users.add_user("seller").await.unwrap();
+        (users, Arc::new(AuctionsMemoryStorage::default()))
+    }
+
+    async fn next_event(events: &mut broadcast::Receiver<SequencedEvent>) -> AuctionEvent {
+        tokio::time::timeout(TIMEOUT, events.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .event
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_auction_is_settled_when_it_ends() {
+        let (users, auctions) = create_storages().await;
+        let events = EventBus::default();
+        let mut receiver = events.subscribe();
+        let scheduler = Scheduler::spawn(
+            Settlement::new(users.clone(), auctions.clone()),
+            events.clone(),
+        )
+        .await
+        .unwrap();
+        let auction = Auction::new("item", 0, DURATION, "seller");
+        let end_time = auction.end_time();
+        let auction_id = auctions.add_auction(auction).await.unwrap();
+        scheduler.schedule(end_time);
+        match next_event(&mut receiver).await {
+            AuctionEvent::Expired {
+                auction_id: expired,
+                ..
+            } => assert_eq!(expired, auction_id),
+            event => panic!("Unexpected event: {:?}", event),
+        }
+        match next_event(&mut receiver).await {
+            AuctionEvent::Settled { outcome, .. } => assert_eq!(outcome, Outcome::Unsold),
+            event => panic!("Unexpected event: {:?}", event),
+        }
+        assert!(SystemTime::now() >= end_time);
+        assert_eq!(users.list_items("seller").await.unwrap(), vec!["item"]);
+    }
+
+    #[tokio::test]
+    async fn test_stored_auctions_are_scheduled_on_spawn() {
+        let (users, auctions) = create_storages().await;
+        let concluded = Auction::new("item1", 0, Duration::ZERO, "seller");
+        let ongoing = Auction::new("item2", 0, DURATION, "seller");
+        auctions.add_auction(concluded).await.unwrap();
+        auctions.add_auction(ongoing).await.unwrap();
+        let events = EventBus::default();
+        let mut receiver = events.subscribe();
+        let _scheduler = Scheduler::spawn(Settlement::new(users.clone(), auctions), events)
+            .await
+            .unwrap();
+        let mut settled = Vec::new();
+        while settled.len() < 2 {
+            if let AuctionEvent::Settled { auction, .. } = next_event(&mut receiver).await {
+                settled.push(auction.item().to_owned());
+            }
+        }
+        assert_eq!(settled, vec!["item1", "item2"]);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_settlements_are_resumed_on_spawn() {
+        let (users, auctions) = create_storages().await;
+        let auction = Auction::new("item", 0, Duration::ZERO, "seller");
+        let auction_id = auctions.add_auction(auction).await.unwrap();
+        auctions.claim_concluded_auctions().await.unwrap();
+        let events = EventBus::default();
+        let mut receiver = events.subscribe();
+        let _scheduler = Scheduler::spawn(Settlement::new(users.clone(), auctions.clone()), events)
+            .await
+            .unwrap();
+        loop {
+            if let AuctionEvent::Settled {
+                auction_id: settled,
+                outcome,
+                ..
+            } = next_event(&mut receiver).await
+            {
+                assert_eq!(settled, auction_id);
+                assert_eq!(outcome, Outcome::Unsold);
+                break;
+            }
+        }
+        assert_eq!(users.list_items("seller").await.unwrap(), vec!["item"]);
+        assert!(auctions.list_settling_auctions().await.unwrap().is_empty());
+    }
+}
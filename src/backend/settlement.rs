@@ -0,0 +1,644 @@
+use crate::backend::{Auction, AuctionId, AuctionsBackend, Bid, UsersBackend};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// How a concluded auction has been settled.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Outcome {
+    /// The item has been sold to the highest bidder.
+    Sold(Bid),
+    /// There were no bids, the item has been returned to the seller.
+    Unsold,
+    /// The highest bid has not reached the reserve price, it has been refunded and the item returned to the seller.
+    ReserveNotMet(Bid),
+    /// The sale could not be completed, the bid has been refunded and the item returned to the seller.
+    Failed(String),
+}
+
+/// A concluded auction removed from the storage together with its outcome.
+#[derive(Clone, Debug)]
+pub struct SettledAuction {
+    pub auction_id: AuctionId,
+    pub auction: Auction,
+    pub outcome: Outcome,
+}
+
+/// Transfers funds and items of concluded auctions.
+///
+/// Auctions stay in the storage marked as settling until their settlement completes and every
+/// transfer is a step applied at most once, so an interrupted settlement is resumed by settling
+/// the auction again.
+pub struct Settlement<UBT, ABT> {
+    users: Arc<UBT>,
+    auctions: Arc<ABT>,
+}
+
+impl<UBT, ABT> Settlement<UBT, ABT>
+where
+    UBT: UsersBackend,
+    ABT: AuctionsBackend,
+{
+    pub fn new(users: Arc<UBT>, auctions: Arc<ABT>) -> Self {
+        Self { users, auctions }
+    }
+
+    /// Claims concluded auctions, settles them and removes them from the storage.
+    /// # Returns
+    /// Outcomes of the settled auctions ordered by their end times or an error if the auctions
+    /// could not be claimed or removed, in which case the claimed auctions are left settling.
+    pub async fn settle_concluded(
+        &self,
+    ) -> Result<Vec<SettledAuction>, Box<dyn Error + Send + Sync>> {
+        let concluded = self.auctions.claim_concluded_auctions().await?;
+        self.settle_claimed(concluded).await
+    }
+
+    /// Settles the auctions which have been left settling, e.g. by a crash, and removes them
+    /// from the storage. The steps which have already been applied are skipped.
+    /// # Returns
+    /// The same as [`Settlement::settle_concluded`].
+    pub async fn resume_interrupted(
+        &self,
+    ) -> Result<Vec<SettledAuction>, Box<dyn Error + Send + Sync>> {
+        let settling = self.auctions.list_settling_auctions().await?;
+        self.settle_claimed(settling).await
+    }
+
+    async fn settle_claimed(
+        &self,
+        auctions: HashMap<AuctionId, Auction>,
+    ) -> Result<Vec<SettledAuction>, Box<dyn Error + Send + Sync>> {
+        let mut settled = Vec::new();
+        for (auction_id, mut auction) in auctions {
+            let losing = auction.unseal();
+            let outcome = match self.release_bids(auction_id, &losing).await {
+                Ok(()) => self.settle(auction_id, &auction).await,
+                Err(error) => self.fail(auction_id, &auction, error).await,
+            };
+            self.auctions.remove_settled_auction(auction_id).await?;
+            settled.push(SettledAuction {
+                auction_id,
+                auction,
+                outcome,
+            });
+        }
+        settled.sort_by_key(|settled| settled.auction.end_time());
+        Ok(settled)
+    }
+
+    /// Returns end times of the ongoing auctions, which are to be settled when they conclude.
+    pub async fn ongoing_end_times(&self) -> Result<Vec<SystemTime>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .auctions
+            .list_ongoing_auctions()
+            .await?
+            .values()
+            .map(Auction::end_time)
+            .collect())
+    }
+
+    /// Settles an auction which has been claimed for settlement, it is not removed from the storage.
+    pub async fn settle(&self, auction_id: AuctionId, auction: &Auction) -> Outcome {
+        let Some(held) = auction.held_bid() else {
+            return match self.return_item(auction_id, auction).await {
+                Ok(()) => Outcome::Unsold,
+                Err(error) => Outcome::Failed(error.to_string()),
+            };
+        };
+        if !auction.reserve_met() {
+            return match self.refund(auction_id, auction, &held).await {
+                Ok(()) => Outcome::ReserveNotMet(Bid {
+                    bidder: held.bidder,
+                    amount: auction.current_price(),
+                }),
+                Err(error) => Outcome::Failed(error),
+            };
+        }
+        // the buyer pays the price, the rest of their maximum bid is released
+        let bid = Bid {
+            bidder: held.bidder.clone(),
+            amount: auction.current_price(),
+        };
+        if held.amount > bid.amount {
+            let excess = held.amount - bid.amount;
+            let released = self
+                .users
+                .release_funds_once(&held.bidder, excess, &step(auction_id, "excess"))
+                .await;
+            if let Err(error) = released {
+                return match self.refund(auction_id, auction, &held).await {
+                    Ok(()) => Outcome::Failed(error.to_string()),
+                    Err(reasons) => Outcome::Failed(format!("{}, {}", error, reasons)),
+                };
+            }
+        }
+        let sale = self
+            .users
+            .settle_sale(
+                auction.seller(),
+                &bid.bidder,
+                auction.item(),
+                bid.amount,
+                &auction_id.to_string(),
+            )
+            .await;
+        let Err(error) = sale else {
+            return Outcome::Sold(bid);
+        };
+        // a failed sale changes nothing, so the auction is settled as if it had no bids
+        match self.refund(auction_id, auction, &bid).await {
+            Ok(()) => Outcome::Failed(error.to_string()),
+            Err(reasons) => Outcome::Failed(format!("{}, {}", error, reasons)),
+        }
+    }
+
+    /// Releases the losing sealed bids.
+    /// # Returns
+    /// Reasons of the failures joined together, if any.
+    async fn release_bids(&self, auction_id: AuctionId, bids: &[Bid]) -> Result<(), String> {
+        let mut reasons = Vec::new();
+        for bid in bids {
+            let released = self
+                .users
+                .release_funds_once(&bid.bidder, bid.amount, &step(auction_id, "losing"))
+                .await;
+            if let Err(error) = released {
+                reasons.push(error.to_string());
+            }
+        }
+        if reasons.is_empty() {
+            Ok(())
+        } else {
+            Err(reasons.join(", "))
+        }
+    }
+
+    /// Settles the auction as failed, the winning bid is refunded and the item returned to the seller.
+    async fn fail(&self, auction_id: AuctionId, auction: &Auction, error: String) -> Outcome {
+        let refunded = match auction.held_bid() {
+            Some(held) => self.refund(auction_id, auction, &held).await,
+            None => self
+                .return_item(auction_id, auction)
+                .await
+                .map_err(|error| error.to_string()),
+        };
+        match refunded {
+            Ok(()) => Outcome::Failed(error),
+            Err(reasons) => Outcome::Failed(format!("{}, {}", error, reasons)),
+        }
+    }
+
+    /// Releases the held bid and returns the item to the seller.
+    /// # Returns
+    /// Reasons of the failures joined together, if any.
+    async fn refund(
+        &self,
+        auction_id: AuctionId,
+        auction: &Auction,
+        bid: &Bid,
+    ) -> Result<(), String> {
+        let mut reasons = Vec::new();
+        let released = self
+            .users
+            .release_funds_once(&bid.bidder, bid.amount, &step(auction_id, "refund"))
+            .await;
+        if let Err(error) = released {
+            reasons.push(error.to_string());
+        }
+        if let Err(error) = self.return_item(auction_id, auction).await {
+            reasons.push(error.to_string());
+        }
+        if reasons.is_empty() {
+            Ok(())
+        } else {
+            Err(reasons.join(", "))
+        }
+    }
+
+    async fn return_item(
+        &self,
+        auction_id: AuctionId,
+        auction: &Auction,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.users
+            .deposit_item_once(
+                auction.seller(),
+                auction.item(),
+                &step(auction_id, "return"),
+            )
+            .await
+    }
+}
+
+/// Returns the id of a settlement step, a user takes part in each step of an auction's settlement at most once.
+fn step(auction_id: AuctionId, name: &str) -> String {
+    format!("{}:{}", auction_id, name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::auctions_memory_storage::AuctionsMemoryStorage;
+    use crate::backend::users_memory_storage::UsersMemoryStorage;
+    use crate::backend::{AuctionType, Funds};
+    use std::time::Duration;
+
+    const DURATION: Duration = Duration::from_millis(50);
+
+    type TestSettlement = Settlement<UsersMemoryStorage, AuctionsMemoryStorage>;
+
+    fn create_settlement() -> TestSettlement {
+        Settlement::new(
+            Arc::new(UsersMemoryStorage::default()),
+            Arc::new(AuctionsMemoryStorage::default()),
+        )
+    }
+
+    async fn add_user(settlement: &TestSettlement, user: &str, funds: Funds) {
+        settlement.users.add_user(user).await.unwrap();
+        settlement.users.deposit_funds(user, funds).await.unwrap();
+    }
+
+    /// Adds an auction which concludes after `DURATION`, bids are held like the service does.
+    async fn add_auction(settlement: &TestSettlement, bids: &[(&str, Funds)]) {
+        let auction = Auction::new("item", 0, DURATION, "seller");
+        place_bids(settlement, auction, bids).await;
+    }
+
+    async fn place_bids(settlement: &TestSettlement, auction: Auction, bids: &[(&str, Funds)]) {
+        let auction_id = settlement.auctions.add_auction(auction).await.unwrap();
+        for &(bidder, amount) in bids {
+            settlement.users.hold_funds(bidder, amount).await.unwrap();
+            let outbid = settlement
+                .auctions
+                .bid_auction(auction_id, bidder, amount)
+                .await
+                .unwrap()
+                .outbid;
+            if let Some(outbid) = outbid {
+                settlement
+                    .users
+                    .release_funds(&outbid.bidder, outbid.amount)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settle_sold_auction() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_user(&settlement, "bidder2", 100).await;
+        add_auction(&settlement, &[("bidder1", 30), ("bidder2", 40)]).await;
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert_eq!(settled.len(), 1);
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::Sold(Bid {
+                bidder: "bidder2".to_string(),
+                amount: 40
+            })
+        );
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 40);
+        assert_eq!(users.show_funds("bidder1").await.unwrap(), 100);
+        assert_eq!(users.show_funds("bidder2").await.unwrap(), 60);
+        assert_eq!(users.show_held_funds("bidder2").await.unwrap(), 0);
+        assert_eq!(users.list_items("bidder2").await.unwrap(), vec!["item"]);
+    }
+
+    #[tokio::test]
+    async fn test_settle_auction_won_by_proxy_bid() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_user(&settlement, "bidder2", 100).await;
+        let auction = Auction::new("item", 0, DURATION, "seller");
+        place_bids(&settlement, auction, &[("bidder1", 30)]).await;
+        let (auction_id, _) = settlement
+            .auctions
+            .list_ongoing_auctions()
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        settlement.users.hold_funds("bidder2", 80).await.unwrap();
+        let placed = settlement
+            .auctions
+            .proxy_bid_auction(auction_id, "bidder2", 80)
+            .await
+            .unwrap();
+        let outbid = placed.outbid.unwrap();
+        settlement
+            .users
+            .release_funds(&outbid.bidder, outbid.amount)
+            .await
+            .unwrap();
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::Sold(Bid {
+                bidder: "bidder2".to_string(),
+                amount: 31
+            })
+        );
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 31);
+        assert_eq!(users.show_funds("bidder2").await.unwrap(), 69);
+        assert_eq!(users.show_held_funds("bidder2").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_settle_unsold_auction() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_auction(&settlement, &[]).await;
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert_eq!(settled[0].outcome, Outcome::Unsold);
+        assert_eq!(
+            settlement.users.list_items("seller").await.unwrap(),
+            vec!["item"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_settle_auction_with_reserve_not_met() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder", 100).await;
+        let auction = Auction::new("item", 0, DURATION, "seller").with_reserve_price(50);
+        place_bids(&settlement, auction, &[("bidder", 49)]).await;
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::ReserveNotMet(Bid {
+                bidder: "bidder".to_string(),
+                amount: 49
+            })
+        );
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 0);
+        assert_eq!(users.list_items("seller").await.unwrap(), vec!["item"]);
+        assert_eq!(users.show_funds("bidder").await.unwrap(), 100);
+        assert_eq!(users.show_held_funds("bidder").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_settle_auction_with_reserve_met() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder", 100).await;
+        let auction = Auction::new("item", 0, DURATION, "seller").with_reserve_price(50);
+        place_bids(&settlement, auction, &[("bidder", 50)]).await;
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert!(matches!(settled[0].outcome, Outcome::Sold(_)));
+        assert_eq!(settlement.users.show_funds("seller").await.unwrap(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_settle_failed_sale() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder", 100).await;
+        add_auction(&settlement, &[("bidder", 30)]).await;
+        settlement
+            .users
+            .deposit_item("bidder", "item")
+            .await
+            .unwrap();
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert!(matches!(settled[0].outcome, Outcome::Failed(_)));
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 0);
+        assert_eq!(users.list_items("seller").await.unwrap(), vec!["item"]);
+        assert_eq!(users.show_funds("bidder").await.unwrap(), 100);
+        assert_eq!(users.show_held_funds("bidder").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_settle_sealed_first_price_auction() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_user(&settlement, "bidder2", 100).await;
+        let auction =
+            Auction::new("item", 10, DURATION, "seller").with_type(AuctionType::SealedFirstPrice);
+        let bids = [("bidder1", 40), ("bidder2", 50), ("bidder1", 45)];
+        place_bids(&settlement, auction, &bids).await;
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::Sold(Bid {
+                bidder: "bidder2".to_string(),
+                amount: 50
+            })
+        );
+        assert_eq!(settled[0].auction.bid_count(), 0);
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 50);
+        assert_eq!(users.show_funds("bidder1").await.unwrap(), 100);
+        assert_eq!(users.show_held_funds("bidder1").await.unwrap(), 0);
+        assert_eq!(users.show_funds("bidder2").await.unwrap(), 50);
+        assert_eq!(users.show_held_funds("bidder2").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_settle_sealed_second_price_auction() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_user(&settlement, "bidder2", 100).await;
+        add_user(&settlement, "bidder3", 100).await;
+        let auction =
+            Auction::new("item", 10, DURATION, "seller").with_type(AuctionType::SealedSecondPrice);
+        let bids = [("bidder1", 30), ("bidder2", 70), ("bidder3", 40)];
+        place_bids(&settlement, auction, &bids).await;
+        tokio::time::sleep(DURATION).await;
+        let settled = settlement.settle_concluded().await.unwrap();
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::Sold(Bid {
+                bidder: "bidder2".to_string(),
+                amount: 40
+            })
+        );
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 40);
+        assert_eq!(users.show_funds("bidder1").await.unwrap(), 100);
+        assert_eq!(users.show_funds("bidder2").await.unwrap(), 60);
+        assert_eq!(users.show_held_funds("bidder2").await.unwrap(), 0);
+        assert_eq!(users.show_funds("bidder3").await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_settle_sealed_second_price_auction_with_single_bid() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_user(&settlement, "bidder2", 100).await;
+        let auction = Auction::new("item", 10, DURATION, "seller")
+            .with_type(AuctionType::SealedSecondPrice)
+            .with_reserve_price(25);
+        place_bids(&settlement, auction, &[("bidder1", 60)]).await;
+        let auction =
+            Auction::new("item", 10, DURATION, "seller").with_type(AuctionType::SealedSecondPrice);
+        place_bids(&settlement, auction, &[("bidder2", 60)]).await;
+        tokio::time::sleep(DURATION).await;
+        let mut outcomes: Vec<_> = settlement
+            .settle_concluded()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|settled| settled.outcome)
+            .collect();
+        outcomes.sort_by_key(|outcome| format!("{:?}", outcome));
+        // the price is the reserve price if there is one, otherwise the starting price
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Sold(Bid {
+                    bidder: "bidder1".to_string(),
+                    amount: 25
+                }),
+                Outcome::Sold(Bid {
+                    bidder: "bidder2".to_string(),
+                    amount: 10
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ongoing_auctions_are_not_settled() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_auction(&settlement, &[]).await;
+        assert!(settlement.settle_concluded().await.unwrap().is_empty());
+        assert!(settlement
+            .users
+            .list_items("seller")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_settlement_interrupted_before_any_transfer() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_auction(&settlement, &[("bidder1", 30)]).await;
+        tokio::time::sleep(DURATION).await;
+        // the auction is claimed, but the settlement stops before transferring anything
+        settlement
+            .auctions
+            .claim_concluded_auctions()
+            .await
+            .unwrap();
+        assert!(settlement.settle_concluded().await.unwrap().is_empty());
+        let settled = settlement.resume_interrupted().await.unwrap();
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::Sold(Bid {
+                bidder: "bidder1".to_string(),
+                amount: 30
+            })
+        );
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 30);
+        assert_eq!(users.show_held_funds("bidder1").await.unwrap(), 0);
+        let auctions = &settlement.auctions;
+        assert!(auctions.list_settling_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_settlement_interrupted_after_transfers() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_user(&settlement, "bidder2", 100).await;
+        add_user(&settlement, "bidder3", 100).await;
+        let auction =
+            Auction::new("item", 10, DURATION, "seller").with_type(AuctionType::SealedSecondPrice);
+        let bids = [("bidder1", 30), ("bidder2", 70), ("bidder3", 40)];
+        place_bids(&settlement, auction, &bids).await;
+        tokio::time::sleep(DURATION).await;
+        // everything is transferred, but the auction is not removed
+        let claimed = settlement
+            .auctions
+            .claim_concluded_auctions()
+            .await
+            .unwrap();
+        for (auction_id, mut auction) in claimed {
+            let losing = auction.unseal();
+            settlement.release_bids(auction_id, &losing).await.unwrap();
+            settlement.settle(auction_id, &auction).await;
+        }
+        let settled = settlement.resume_interrupted().await.unwrap();
+        assert_eq!(
+            settled[0].outcome,
+            Outcome::Sold(Bid {
+                bidder: "bidder2".to_string(),
+                amount: 40
+            })
+        );
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("seller").await.unwrap(), 40);
+        assert_eq!(users.show_funds("bidder1").await.unwrap(), 100);
+        assert_eq!(users.show_funds("bidder2").await.unwrap(), 60);
+        assert_eq!(users.show_held_funds("bidder2").await.unwrap(), 0);
+        assert_eq!(users.show_funds("bidder3").await.unwrap(), 100);
+        assert_eq!(users.list_items("bidder2").await.unwrap(), vec!["item"]);
+        let auctions = &settlement.auctions;
+        assert!(auctions.list_settling_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_failed_settlement_does_not_repeat_refund() {
+        let settlement = create_settlement();
+        add_user(&settlement, "seller", 0).await;
+        add_user(&settlement, "bidder1", 100).await;
+        add_auction(&settlement, &[("bidder1", 30)]).await;
+        // the held funds of another bid must stay held
+        settlement.users.hold_funds("bidder1", 50).await.unwrap();
+        settlement
+            .users
+            .deposit_item("bidder1", "item")
+            .await
+            .unwrap();
+        tokio::time::sleep(DURATION).await;
+        let claimed = settlement
+            .auctions
+            .claim_concluded_auctions()
+            .await
+            .unwrap();
+        for (auction_id, auction) in claimed {
+            let outcome = settlement.settle(auction_id, &auction).await;
+            assert!(matches!(outcome, Outcome::Failed(_)));
+        }
+        settlement
+            .users
+            .withdraw_item("bidder1", "item")
+            .await
+            .unwrap();
+        let settled = settlement.resume_interrupted().await.unwrap();
+        assert!(matches!(settled[0].outcome, Outcome::Failed(_)));
+        let users = &settlement.users;
+        assert_eq!(users.show_funds("bidder1").await.unwrap(), 50);
+        assert_eq!(users.show_held_funds("bidder1").await.unwrap(), 50);
+        assert_eq!(users.list_items("seller").await.unwrap(), vec!["item"]);
+        assert!(users.list_items("bidder1").await.unwrap().is_empty());
+    }
+}
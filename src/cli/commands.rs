@@ -126,30 +126,100 @@ pub enum AuctionsCommands {
         #[arg(short, long)]
         item: String,
 
-        /// starting price
+        /// starting price, the minimum opening bid
         #[arg(short, long)]
         starting_price: u64,
 
         /// duration in seconds
         #[arg(short, long)]
         duration: u64,
+
+        /// minimum amount by which a bid has to exceed the highest bid
+        #[arg(long, conflicts_with = "min_increment_percent")]
+        min_increment: Option<u64>,
+
+        /// minimum percentage of the highest bid by which a bid has to exceed it
+        #[arg(long)]
+        min_increment_percent: Option<u32>,
+
+        /// hidden price below which the item is not sold
+        #[arg(long)]
+        reserve_price: Option<u64>,
+
+        /// price for which the item can be bought at once, ending the auction
+        #[arg(long)]
+        buy_now_price: Option<u64>,
+
+        /// bids placed within this many seconds before the end extend the auction
+        #[arg(long, requires = "soft_close_extension")]
+        soft_close_window: Option<u64>,
+
+        /// seconds by which a late bid pushes out the end of the auction
+        #[arg(long, requires = "soft_close_window")]
+        soft_close_extension: Option<u64>,
+
+        /// maximum number of seconds by which late bids may extend the auction in total
+        #[arg(long, requires = "soft_close_window")]
+        soft_close_max_extension: Option<u64>,
+
+        /// sell in a Dutch auction whose price drops from the starting price, never below this floor price, the first bid wins
+        #[arg(long, conflicts_with_all = ["min_increment", "min_increment_percent", "reserve_price", "buy_now_price", "soft_close_window"])]
+        dutch_floor_price: Option<u64>,
+
+        /// amount by which the Dutch auction's price drops at every step, without it the price drops linearly
+        #[arg(long, requires_all = ["dutch_floor_price", "dutch_step_interval"])]
+        dutch_step_amount: Option<u64>,
+
+        /// seconds between the price drops of a Dutch auction
+        #[arg(long, requires = "dutch_step_amount")]
+        dutch_step_interval: Option<u64>,
+
+        /// seal the bids until the end, the highest bid wins and pays its own amount
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["sealed_second_price", "dutch_floor_price", "min_increment", "min_increment_percent", "buy_now_price", "soft_close_window"])]
+        sealed_first_price: bool,
+
+        /// seal the bids until the end, the highest bid wins and pays the second highest bid
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["dutch_floor_price", "min_increment", "min_increment_percent", "buy_now_price", "soft_close_window"])]
+        sealed_second_price: bool,
     },
     /// Bid on an auction, fails if not logged in or if the auction does not exist
     Bid {
         /// auction's id
         #[arg(short, long)]
-        auction_id: u64,
+        auction_id: String,
 
-        /// bid amount, fails if the amount is lower than the current bid or the starting price
-        #[arg(short, long)]
+        /// bid amount, fails if the amount is lower than the starting price or the highest bid raised by the minimum increment,
+        /// a sealed bid replaces the user's previous one
+        #[arg(short = 'm', long)]
         amount: u64,
+
+        /// bid automatically in minimum increments up to the amount, which stays hidden
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
+        proxy: bool,
+    },
+    /// Buy an item for the auction's buy now price, fails if not logged in or if buy now is not offered
+    BuyNow {
+        /// auction's id
+        #[arg(short, long)]
+        auction_id: String,
     },
     /// Close an auction, fails if not logged in, the auction does not exist or user is not the auction's owner
     Close {
         /// auction's id
         #[arg(short, long)]
-        auction_id: u64,
+        auction_id: String,
     },
-    /// Watch all user's auctions, fails if not logged in, refreshes user's token if it's expired
+    /// Watch all user's auctions and their results, fails if not logged in
     Watch,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_arguments() {
+        Cli::command().debug_assert();
+    }
+}
@@ -1,24 +1,87 @@
+use backend_proto::backend_client::BackendClient;
+use backend_proto::{
+    sell_item_request, Auction, AuctionType, BidItemRequest, BuyNowRequest, CloseAuctionRequest,
+    DepositFundsRequest, DepositItemRequest, PriceSchedule, PriceStep, SellItemRequest, SoftClose,
+    WithdrawFundsRequest, WithdrawItemRequest,
+};
 use clap::Parser;
 use client_session_proto::client_session_client::ClientSessionClient;
 use client_session_proto::{ChangePasswordRequest, LoginRequest, RegisterRequest};
 use std::env;
+use tonic::Request;
 
+pub mod backend_proto {
+    tonic::include_proto!("auction_house_rs.backend");
+}
 pub mod client_session_proto {
     tonic::include_proto!("auction_house_rs.session.client");
 }
 mod commands;
 
+/// Creates a request carrying the user's token, fails if no token was provided.
+fn authorized_request<T>(
+    message: T,
+    token: &Option<String>,
+) -> Result<Request<T>, Box<dyn std::error::Error>> {
+    let Some(token) = token else {
+        return Err("Token is required, log in first".into());
+    };
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("authorization", format!("Bearer {}", token).parse()?);
+    Ok(request)
+}
+
+/// Formats an auction as a single line of the auctions list.
+fn format_auction(auction: &Auction) -> String {
+    let ends_at = chrono::DateTime::from_timestamp(auction.ends_at as i64, 0)
+        .map_or(auction.ends_at.to_string(), |ends_at| ends_at.to_rfc3339());
+    let mut line = format!(
+        "{}: {} sold by {} for {}",
+        auction.id, auction.item, auction.seller, auction.price
+    );
+    match AuctionType::try_from(auction.auction_type) {
+        Ok(AuctionType::Dutch) => line += " in a Dutch auction",
+        Ok(AuctionType::SealedFirstPrice) => line += " in a sealed first-price auction",
+        Ok(AuctionType::SealedSecondPrice) => line += " in a sealed second-price auction",
+        _ => {}
+    }
+    if !auction.buyer.is_empty() {
+        line += &format!(" bid by {}", auction.buyer);
+    }
+    line += &format!(", minimum bid {}, ends at {}", auction.min_bid, ends_at);
+    match auction.reserve_met {
+        Some(true) => line += ", reserve met",
+        Some(false) => line += ", reserve not met",
+        None => {}
+    }
+    if let Some(bid_count) = auction.bid_count {
+        line += &format!(", sealed bids: {}", bid_count);
+    }
+    if let Some(buy_now_price) = auction.buy_now_price {
+        line += &format!(", buy now for {}", buy_now_price);
+    }
+    line
+}
+
+/// Formats the auctions list, one auction per line.
+fn format_auctions(auctions: &[Auction]) -> String {
+    if auctions.is_empty() {
+        return "There are no auctions".to_string();
+    }
+    auctions
+        .iter()
+        .map(format_auction)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = commands::Cli::parse();
 
-    let token = if let Some(token) = cli.token {
-        Some(token)
-    } else if let Ok(token) = env::var("AUCTION_HOUSE_TOKEN") {
-        Some(token)
-    } else {
-        None
-    };
+    let token = cli.token.or_else(|| env::var("AUCTION_HOUSE_TOKEN").ok());
 
     // TODO: refactor this
     let result = match &cli.command {
@@ -42,41 +105,234 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     format!("Your token is: {}", response.into_inner().token)
                 }
                 commands::UserCommands::Logout => {
-                    let request = tonic::Request::new(());
+                    let request = authorized_request((), &token)?;
                     let _ = client.logout(request).await?;
-                    format!("You have been logged out")
+                    "You have been logged out".to_string()
                 }
                 commands::UserCommands::Delete => {
-                    let request = tonic::Request::new(());
-                    let _ = client.logout(request).await?;
-                    format!("Your account has been deleted")
+                    let request = authorized_request((), &token)?;
+                    let _ = client.delete_account(request).await?;
+                    "Your account has been deleted".to_string()
                 }
                 commands::UserCommands::ChangePassword {
                     old_password,
                     new_password,
                 } => {
-                    let request = tonic::Request::new(ChangePasswordRequest {
-                        old_password: old_password.clone(),
-                        new_password: new_password.clone(),
-                    });
+                    let request = authorized_request(
+                        ChangePasswordRequest {
+                            old_password: old_password.clone(),
+                            new_password: new_password.clone(),
+                        },
+                        &token,
+                    )?;
                     let response = client.change_password(request).await?;
                     format!("Your token is: {}", response.into_inner().token)
                 }
                 commands::UserCommands::RefreshToken => {
-                    let request = tonic::Request::new(());
+                    let request = authorized_request((), &token)?;
                     let response = client.refresh_token(request).await?;
                     format!("Your token is: {}", response.into_inner().token)
                 }
             }
         }
         commands::Commands::Funds { command } => {
-            unimplemented!()
+            let mut client = BackendClient::connect("http://[::1]:50052").await?;
+            match &command {
+                commands::FundsCommands::Balance => {
+                    let request = authorized_request((), &token)?;
+                    let funds = client.show_funds(request).await?.into_inner();
+                    format!(
+                        "Your balance is: {}, held by your bids: {}",
+                        funds.funds, funds.held_funds
+                    )
+                }
+                commands::FundsCommands::Deposit { amount } => {
+                    let request =
+                        authorized_request(DepositFundsRequest { amount: *amount }, &token)?;
+                    client.deposit_funds(request).await?;
+                    format!("{} has been deposited", amount)
+                }
+                commands::FundsCommands::Withdraw { amount } => {
+                    let request =
+                        authorized_request(WithdrawFundsRequest { amount: *amount }, &token)?;
+                    client.withdraw_funds(request).await?;
+                    format!("{} has been withdrawn", amount)
+                }
+            }
         }
         commands::Commands::Items { command } => {
-            unimplemented!()
+            let mut client = BackendClient::connect("http://[::1]:50052").await?;
+            match &command {
+                commands::ItemsCommands::List => {
+                    let request = authorized_request((), &token)?;
+                    let items = client.show_items(request).await?.into_inner().items;
+                    if items.is_empty() {
+                        "You have no items".to_string()
+                    } else {
+                        items.join("\n")
+                    }
+                }
+                commands::ItemsCommands::Deposit { name } => {
+                    let request =
+                        authorized_request(DepositItemRequest { item: name.clone() }, &token)?;
+                    client.deposit_item(request).await?;
+                    format!("{} has been deposited", name)
+                }
+                commands::ItemsCommands::Withdraw { name } => {
+                    let request =
+                        authorized_request(WithdrawItemRequest { item: name.clone() }, &token)?;
+                    client.withdraw_item(request).await?;
+                    format!("{} has been withdrawn", name)
+                }
+            }
         }
         commands::Commands::Auctions { command } => {
-            unimplemented!()
+            let mut client = BackendClient::connect("http://[::1]:50052").await?;
+            match &command {
+                commands::AuctionsCommands::List { watch: false } => {
+                    let response = client.list_auctions(Request::new(())).await?;
+                    format_auctions(&response.into_inner().auctions)
+                }
+                commands::AuctionsCommands::List { watch: true } => {
+                    let mut stream = client.watch_auctions(Request::new(())).await?.into_inner();
+                    while let Some(response) = stream.message().await? {
+                        println!("{}\n", format_auctions(&response.auctions));
+                    }
+                    "The auctions feed has ended".to_string()
+                }
+                commands::AuctionsCommands::Create {
+                    item,
+                    starting_price,
+                    duration,
+                    min_increment,
+                    min_increment_percent,
+                    reserve_price,
+                    buy_now_price,
+                    soft_close_window,
+                    soft_close_extension,
+                    soft_close_max_extension,
+                    dutch_floor_price,
+                    dutch_step_amount,
+                    dutch_step_interval,
+                    sealed_first_price,
+                    sealed_second_price,
+                } => {
+                    let min_increment = match (min_increment, min_increment_percent) {
+                        (Some(amount), _) => {
+                            Some(sell_item_request::MinIncrement::MinIncrementAmount(*amount))
+                        }
+                        (None, Some(percent)) => Some(
+                            sell_item_request::MinIncrement::MinIncrementPercent(*percent),
+                        ),
+                        (None, None) => None,
+                    };
+                    let soft_close =
+                        soft_close_window
+                            .zip(*soft_close_extension)
+                            .map(|(window, extension)| SoftClose {
+                                window,
+                                extension,
+                                max_extension: *soft_close_max_extension,
+                            });
+                    let price_schedule = dutch_floor_price.map(|floor_price| PriceSchedule {
+                        floor_price,
+                        step: dutch_step_amount.map(|amount| PriceStep {
+                            amount,
+                            interval: dutch_step_interval.unwrap_or_default(),
+                        }),
+                    });
+                    let auction_type = if price_schedule.is_some() {
+                        AuctionType::Dutch
+                    } else if *sealed_first_price {
+                        AuctionType::SealedFirstPrice
+                    } else if *sealed_second_price {
+                        AuctionType::SealedSecondPrice
+                    } else {
+                        AuctionType::English
+                    };
+                    let request = authorized_request(
+                        SellItemRequest {
+                            item: item.clone(),
+                            price: *starting_price,
+                            duration: *duration,
+                            min_increment,
+                            reserve_price: *reserve_price,
+                            buy_now_price: *buy_now_price,
+                            soft_close,
+                            auction_type: auction_type.into(),
+                            price_schedule,
+                        },
+                        &token,
+                    )?;
+                    let auction = client.sell_item(request).await?.into_inner();
+                    format!("Auction has been created: {}", format_auction(&auction))
+                }
+                commands::AuctionsCommands::Bid {
+                    auction_id,
+                    amount,
+                    proxy,
+                } => {
+                    let request = authorized_request(
+                        BidItemRequest {
+                            auction_id: auction_id.clone(),
+                            price: *amount,
+                            proxy: *proxy,
+                        },
+                        &token,
+                    )?;
+                    client.bid_item(request).await?;
+                    if *proxy {
+                        format!("You have bid on {} up to {}", auction_id, amount)
+                    } else {
+                        format!("You have bid {} on {}", amount, auction_id)
+                    }
+                }
+                commands::AuctionsCommands::BuyNow { auction_id } => {
+                    let request = authorized_request(
+                        BuyNowRequest {
+                            auction_id: auction_id.clone(),
+                        },
+                        &token,
+                    )?;
+                    client.buy_now(request).await?;
+                    format!("You have bought the item of {}", auction_id)
+                }
+                commands::AuctionsCommands::Close { auction_id } => {
+                    let request = authorized_request(
+                        CloseAuctionRequest {
+                            auction_id: auction_id.clone(),
+                        },
+                        &token,
+                    )?;
+                    client.close_auction(request).await?;
+                    format!("Auction {} has been closed", auction_id)
+                }
+                commands::AuctionsCommands::Watch => {
+                    let request = authorized_request((), &token)?;
+                    let mut stream = client.watch_user_auctions(request).await?.into_inner();
+                    while let Some(response) = stream.message().await? {
+                        if !response.auctions.is_empty() {
+                            println!("{}", format_auctions(&response.auctions));
+                        }
+                        for auction_id in response.extended_auctions {
+                            println!("Auction {} has been extended by a late bid", auction_id);
+                        }
+                        for auction_id in response.expired_auctions {
+                            println!("Auction {} has ended", auction_id);
+                        }
+                        for auction_id in response.finalized_auctions {
+                            println!("Auction {} has been settled", auction_id);
+                        }
+                        for auction_id in response.reserve_not_met_auctions {
+                            println!(
+                                "Auction {} has ended below the reserve price, the item has not been sold",
+                                auction_id
+                            );
+                        }
+                    }
+                    "Your auctions feed has ended".to_string()
+                }
+            }
         }
     };
 
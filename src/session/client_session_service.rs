@@ -1,8 +1,8 @@
-use crate::token_engine::TokenBroker;
+use crate::token_engine::{TokenBroker, VerifiedToken};
 use crate::user_credentials::{memory_storage::MemoryStorage, UserCredentials};
 use client_proto::client_session_server::{ClientSession, ClientSessionServer};
 use client_proto::{ChangePasswordRequest, LoginRequest, RegisterRequest, TokenResponse};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tonic::{Request, Response, Status};
 pub mod client_proto {
     tonic::include_proto!("auction_house_rs.session.client");
@@ -10,25 +10,34 @@ pub mod client_proto {
 
 pub fn create_client_session_service(
     tokens: Arc<TokenBroker>,
+    credentials: Arc<dyn UserCredentials>,
 ) -> ClientSessionServer<ClientSessionService> {
-    ClientSessionServer::new(ClientSessionService::new(tokens))
+    ClientSessionServer::new(ClientSessionService::new(tokens).with_credentials(credentials))
 }
 
 pub struct ClientSessionService {
     tokens: Arc<TokenBroker>,
-    credentials: Arc<Mutex<dyn UserCredentials + Send>>,
+    credentials: Arc<dyn UserCredentials>,
 }
 
 const AUTH_HEADER: &str = "authorization";
+const BEARER_PREFIX: &str = "Bearer ";
 
 impl ClientSessionService {
     fn new(tokens: Arc<TokenBroker>) -> Self {
         Self {
             tokens,
-            credentials: Arc::new(Mutex::new(MemoryStorage::default())),
+            credentials: Arc::new(MemoryStorage::default()),
         }
     }
 
+    /// Replaces the default in-memory credentials storage.
+    fn with_credentials(mut self, credentials: Arc<dyn UserCredentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    #[allow(clippy::result_large_err)]
     fn get_token_response(&self, user: &str) -> Result<Response<TokenResponse>, Status> {
         if let Ok(token) = self.tokens.create_new_token(user) {
             Ok(Response::new(TokenResponse { token }))
@@ -40,32 +49,21 @@ impl ClientSessionService {
         }
     }
 
-    // TODO turn this into a macro
-    fn exec_authorized<TReq, TRsp, F>(
-        &self,
-        request: Request<TReq>,
-        callback: F,
-    ) -> Result<Response<TRsp>, Status>
-    where
-        F: Fn(Request<TReq>, &str, &str) -> Result<Response<TRsp>, Status>,
-    {
-        let auth_metadata = request.metadata().get(AUTH_HEADER);
-        if let None = auth_metadata {
+    /// Verifies the request's bearer token.
+    #[allow(clippy::result_large_err)]
+    fn authorize<T>(&self, request: &Request<T>) -> Result<VerifiedToken, Status> {
+        let token = request
+            .metadata()
+            .get(AUTH_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix(BEARER_PREFIX));
+        let Some(token) = token else {
             return Err(Status::new(
-                tonic::Code::Internal,
+                tonic::Code::Unauthenticated,
                 "Failed to get token".to_string(),
             ));
-        }
-        let token = auth_metadata.unwrap().to_owned();
-        let result = self.tokens.verify_token(&token.to_str().unwrap()[7..]); // skip "Bearer "
-        if let Ok(user) = result {
-            callback(request, &user, &token.to_str().unwrap())
-        } else {
-            Err(Status::new(
-                tonic::Code::PermissionDenied,
-                "Invalid token".to_string(),
-            ))
-        }
+        };
+        Ok(self.tokens.verify_token(token)?)
     }
 }
 
@@ -76,11 +74,12 @@ impl ClientSession for ClientSessionService {
         request: Request<RegisterRequest>,
     ) -> Result<Response<TokenResponse>, Status> {
         let data = request.into_inner();
+        if let Err(status) = self
+            .credentials
+            .add_user(&data.username, &data.password)
+            .await
         {
-            let mut users = self.credentials.lock().unwrap();
-            if let Err(status) = users.add_user(&data.username, &data.password) {
-                return Err(Status::new(tonic::Code::AlreadyExists, status.to_string()));
-            }
+            return Err(Status::new(tonic::Code::AlreadyExists, status.to_string()));
         }
         self.get_token_response(&data.username)
     }
@@ -90,67 +89,70 @@ impl ClientSession for ClientSessionService {
         request: Request<LoginRequest>,
     ) -> Result<Response<TokenResponse>, Status> {
         let data = request.into_inner();
+        if let Err(status) = self
+            .credentials
+            .verify_user(&data.username, &data.password)
+            .await
         {
-            let users = self.credentials.lock().unwrap();
-            if let Err(status) = users.verify_user(&data.username, &data.password) {
-                return Err(Status::new(
-                    tonic::Code::PermissionDenied,
-                    status.to_string(),
-                ));
-            }
+            return Err(Status::new(
+                tonic::Code::PermissionDenied,
+                status.to_string(),
+            ));
         }
         self.get_token_response(&data.username)
     }
 
-    async fn logout(&self, _request: Request<()>) -> Result<Response<()>, Status> {
-        // TODO: put a token into a blacklist
+    async fn logout(&self, request: Request<()>) -> Result<Response<()>, Status> {
+        let token = self.authorize(&request)?;
+        self.tokens.revoke_token(&token);
         Ok(Response::new(()))
     }
 
     async fn delete_account(&self, request: Request<()>) -> Result<Response<()>, Status> {
-        self.exec_authorized(request, |_, user, _| {
-            let mut users = self.credentials.lock().unwrap();
-            users.remove_user(&user);
-            Ok(Response::new(()))
-        })
+        let token = self.authorize(&request)?;
+        if let Err(status) = self.credentials.remove_user(&token.user).await {
+            return Err(Status::new(tonic::Code::Internal, status.to_string()));
+        }
+        self.tokens.revoke_user_tokens(&token.user);
+        Ok(Response::new(()))
     }
 
     async fn change_password(
         &self,
         request: Request<ChangePasswordRequest>,
     ) -> Result<Response<TokenResponse>, Status> {
-        self.exec_authorized(request, |request, user, _| {
-            let data = request.into_inner();
-            let mut users = self.credentials.lock().unwrap();
-            if let Err(status) = users.verify_user(&user, &data.old_password) {
-                return Err(Status::new(
-                    tonic::Code::PermissionDenied,
-                    status.to_string(),
-                ));
-            }
-            if let Err(status) = users.update_user(&user, &data.new_password) {
-                return Err(Status::new(tonic::Code::Internal, status.to_string()));
-            }
-            // TODO: invalidate the old token!
-            self.get_token_response(&user) // generate new token
-        })
+        let token = self.authorize(&request)?;
+        let user = &token.user;
+        let data = request.into_inner();
+        if let Err(status) = self.credentials.verify_user(user, &data.old_password).await {
+            return Err(Status::new(
+                tonic::Code::PermissionDenied,
+                status.to_string(),
+            ));
+        }
+        if let Err(status) = self.credentials.update_user(user, &data.new_password).await {
+            return Err(Status::new(tonic::Code::Internal, status.to_string()));
+        }
+        // sessions started with the old password are no longer valid
+        self.tokens.revoke_user_tokens(user);
+        self.get_token_response(user) // generate new token
     }
 
     async fn refresh_token(&self, request: Request<()>) -> Result<Response<TokenResponse>, Status> {
-        self.exec_authorized(request, |_request, user, _token| {
-            // TODO: invalidate the old token!
-            self.get_token_response(&user)
-        })
+        let token = self.authorize(&request)?;
+        self.tokens.revoke_token(&token);
+        self.get_token_response(&token.user)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::token_engine::TEST_KEY;
 
     #[tokio::test]
     async fn test_register() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -162,7 +164,7 @@ mod test {
 
     #[tokio::test]
     async fn test_try_register_twice() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -178,7 +180,7 @@ mod test {
 
     #[tokio::test]
     async fn test_login() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -195,7 +197,7 @@ mod test {
 
     #[tokio::test]
     async fn test_remove_user() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -224,7 +226,7 @@ mod test {
 
     #[tokio::test]
     async fn test_try_remove_user_without_login() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -240,7 +242,7 @@ mod test {
 
     #[tokio::test]
     async fn test_change_password() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -273,7 +275,7 @@ mod test {
 
     #[tokio::test]
     async fn test_try_change_password_without_login() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -292,7 +294,7 @@ mod test {
 
     #[tokio::test]
     async fn test_login_after_changing_password() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -324,7 +326,7 @@ mod test {
 
     #[tokio::test]
     async fn test_refresh_token_after_login() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let request = tonic::Request::new(RegisterRequest {
             username: "user".into(),
@@ -351,7 +353,7 @@ mod test {
 
     #[tokio::test]
     async fn test_try_refresh_token_without_login() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = ClientSessionService::new(tokens.clone());
         let mut request = tonic::Request::new(());
         request
@@ -359,4 +361,96 @@ mod test {
             .append(AUTH_HEADER, "Bearer invalid token".parse().unwrap());
         assert!(service.refresh_token(request).await.is_err());
     }
+
+    async fn register_user(service: &ClientSessionService) -> String {
+        let request = tonic::Request::new(RegisterRequest {
+            username: "user".into(),
+            password: "password".into(),
+        });
+        service.register(request).await.unwrap().into_inner().token
+    }
+
+    fn authorized_request<T>(message: T, token: &str) -> Request<T> {
+        let mut request = tonic::Request::new(message);
+        request
+            .metadata_mut()
+            .append(AUTH_HEADER, format!("Bearer {}", token).parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_logout() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = ClientSessionService::new(tokens.clone());
+        let token = register_user(&service).await;
+        let _ = service
+            .logout(authorized_request((), &token))
+            .await
+            .unwrap();
+        assert!(tokens.verify_token(&token).is_err());
+        let status = service
+            .refresh_token(authorized_request((), &token))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_try_logout_without_login() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = ClientSessionService::new(tokens.clone());
+        assert!(service.logout(tonic::Request::new(())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_revokes_old_token() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = ClientSessionService::new(tokens.clone());
+        let token = register_user(&service).await;
+        let _ = service
+            .refresh_token(authorized_request((), &token))
+            .await
+            .unwrap();
+        assert!(tokens.verify_token(&token).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_change_password_revokes_all_user_tokens() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = ClientSessionService::new(tokens.clone());
+        let token = register_user(&service).await;
+        let request = tonic::Request::new(LoginRequest {
+            username: "user".into(),
+            password: "password".into(),
+        });
+        let other_token = service.login(request).await.unwrap().into_inner().token;
+        let request = authorized_request(
+            ChangePasswordRequest {
+                old_password: "password".into(),
+                new_password: "new password".into(),
+            },
+            &token,
+        );
+        let new_token = service
+            .change_password(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .token;
+        assert!(tokens.verify_token(&token).is_err());
+        assert!(tokens.verify_token(&other_token).is_err());
+        assert!(tokens.verify_token(&new_token).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_revokes_user_tokens() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = ClientSessionService::new(tokens.clone());
+        let token = register_user(&service).await;
+        let _ = service
+            .delete_account(authorized_request((), &token))
+            .await
+            .unwrap();
+        assert!(tokens.verify_token(&token).is_err());
+    }
 }
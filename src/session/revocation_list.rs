@@ -0,0 +1,17 @@
+pub mod memory_storage;
+
+/// Storage of issued and revoked tokens, tokens are identified by their `jti` claim.
+///
+/// Entries are kept only until the tokens expire, expiration times are given in seconds since the Unix epoch.
+pub trait RevocationList {
+    /// Records a newly issued token, so it can be revoked together with all other user's tokens.
+    fn add_token(&mut self, user: &str, token_id: &str, expires_at: u64);
+
+    /// Revokes a single token.
+    fn revoke_token(&mut self, user: &str, token_id: &str, expires_at: u64);
+
+    /// Revokes all outstanding tokens of the user.
+    fn revoke_user_tokens(&mut self, user: &str);
+
+    fn is_revoked(&self, token_id: &str) -> bool;
+}
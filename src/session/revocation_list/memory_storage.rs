@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type TokenIds = HashMap<String, u64>;
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    issued: HashMap<String, TokenIds>,
+    revoked: TokenIds,
+}
+
+impl MemoryStorage {
+    fn remove_expired(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        self.revoked.retain(|_, expires_at| *expires_at > now);
+        for tokens in self.issued.values_mut() {
+            tokens.retain(|_, expires_at| *expires_at > now);
+        }
+        self.issued.retain(|_, tokens| !tokens.is_empty());
+    }
+}
+
+impl super::RevocationList for MemoryStorage {
+    fn add_token(&mut self, user: &str, token_id: &str, expires_at: u64) {
+        self.remove_expired();
+        self.issued
+            .entry(user.to_owned())
+            .or_default()
+            .insert(token_id.to_owned(), expires_at);
+    }
+
+    fn revoke_token(&mut self, user: &str, token_id: &str, expires_at: u64) {
+        self.remove_expired();
+        if let Some(tokens) = self.issued.get_mut(user) {
+            tokens.remove(token_id);
+        }
+        self.revoked.insert(token_id.to_owned(), expires_at);
+    }
+
+    fn revoke_user_tokens(&mut self, user: &str) {
+        self.remove_expired();
+        if let Some(tokens) = self.issued.remove(user) {
+            self.revoked.extend(tokens);
+        }
+    }
+
+    fn is_revoked(&self, token_id: &str) -> bool {
+        self.revoked.contains_key(token_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::revocation_list::RevocationList;
+
+    const FAR_FUTURE: u64 = u64::MAX;
+
+    #[test]
+    fn test_revoke_token() {
+        let mut tokens = MemoryStorage::default();
+        tokens.add_token("user", "token1", FAR_FUTURE);
+        tokens.add_token("user", "token2", FAR_FUTURE);
+        tokens.revoke_token("user", "token1", FAR_FUTURE);
+        assert!(tokens.is_revoked("token1"));
+        assert!(!tokens.is_revoked("token2"));
+    }
+
+    #[test]
+    fn test_revoke_user_tokens() {
+        let mut tokens = MemoryStorage::default();
+        tokens.add_token("user", "token1", FAR_FUTURE);
+        tokens.add_token("user", "token2", FAR_FUTURE);
+        tokens.add_token("other user", "token3", FAR_FUTURE);
+        tokens.revoke_user_tokens("user");
+        assert!(tokens.is_revoked("token1"));
+        assert!(tokens.is_revoked("token2"));
+        assert!(!tokens.is_revoked("token3"));
+    }
+
+    #[test]
+    fn test_expired_tokens_are_removed() {
+        let mut tokens = MemoryStorage::default();
+        tokens.add_token("user", "token1", 0);
+        tokens.revoke_token("user", "token2", 0);
+        tokens.add_token("user", "token3", FAR_FUTURE);
+        assert!(!tokens.is_revoked("token2"));
+        assert_eq!(tokens.issued["user"].len(), 1);
+    }
+}
@@ -0,0 +1,281 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::{general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use jwt::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Environment variable holding the key used to sign tokens.
+pub const SIGNING_KEY_ENV: &str = "AUCTION_HOUSE_SIGNING_KEY";
+
+const MIN_KEY_LENGTH: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Algorithm {
+    /// HMAC with SHA-256, the key is a shared secret
+    Hs256,
+    /// ECDSA with the P-256 curve, the key is a PKCS#8 PEM encoded private key
+    Es256,
+}
+
+enum Key {
+    Hs256(Hmac<Sha256>),
+    Es256(p256::ecdsa::SigningKey),
+}
+
+/// Key used to sign and verify tokens together with its id, which is put into the `kid` header.
+pub struct SigningKey {
+    id: String,
+    key: Key,
+}
+
+/// Public part of an asymmetric signing key, fields follow the JWK format.
+#[derive(Debug, PartialEq)]
+pub struct PublicKey {
+    pub key_id: String,
+    pub algorithm: String,
+    pub curve: String,
+    /// Base64url encoded x coordinate of the public point.
+    pub x: String,
+    /// Base64url encoded y coordinate of the public point.
+    pub y: String,
+}
+
+fn digest_id(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl SigningKey {
+    pub fn hs256(key: &[u8]) -> Self {
+        Self {
+            id: digest_id(key),
+            key: Key::Hs256(Hmac::new_from_slice(key).unwrap()), // HMAC accepts keys of any length
+        }
+    }
+
+    pub fn parse(algorithm: Algorithm, key: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        if key.len() < MIN_KEY_LENGTH {
+            return Err(
+                format!("Signing key must be at least {} bytes long", MIN_KEY_LENGTH).into(),
+            );
+        }
+        match algorithm {
+            Algorithm::Hs256 => Ok(Self::hs256(key)),
+            Algorithm::Es256 => {
+                let pem = std::str::from_utf8(key)?;
+                let key = p256::ecdsa::SigningKey::from_pkcs8_pem(pem)
+                    .map_err(|error| format!("Invalid ES256 private key: {}", error))?;
+                let public_key = key.verifying_key().to_encoded_point(false);
+                Ok(Self {
+                    id: digest_id(public_key.as_bytes()),
+                    key: Key::Es256(key),
+                })
+            }
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the public key, which can be used to verify tokens, if the key is asymmetric.
+    pub fn public_key(&self) -> Option<PublicKey> {
+        match &self.key {
+            Key::Hs256(_) => None,
+            Key::Es256(key) => {
+                let point = key.verifying_key().to_encoded_point(false);
+                Some(PublicKey {
+                    key_id: self.id.clone(),
+                    algorithm: "ES256".to_string(),
+                    curve: "P-256".to_string(),
+                    x: URL_SAFE_NO_PAD.encode(point.x()?),
+                    y: URL_SAFE_NO_PAD.encode(point.y()?),
+                })
+            }
+        }
+    }
+}
+
+impl SigningAlgorithm for SigningKey {
+    fn algorithm_type(&self) -> AlgorithmType {
+        match &self.key {
+            Key::Hs256(_) => AlgorithmType::Hs256,
+            Key::Es256(_) => AlgorithmType::Es256,
+        }
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, jwt::Error> {
+        match &self.key {
+            Key::Hs256(key) => key.sign(header, claims),
+            Key::Es256(key) => {
+                let message = format!("{}.{}", header, claims);
+                let signature: p256::ecdsa::Signature = key.sign(message.as_bytes());
+                Ok(URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+            }
+        }
+    }
+}
+
+impl VerifyingAlgorithm for SigningKey {
+    fn algorithm_type(&self) -> AlgorithmType {
+        SigningAlgorithm::algorithm_type(self)
+    }
+
+    fn verify_bytes(
+        &self,
+        header: &str,
+        claims: &str,
+        signature: &[u8],
+    ) -> Result<bool, jwt::Error> {
+        match &self.key {
+            Key::Hs256(key) => key.verify_bytes(header, claims, signature),
+            Key::Es256(key) => {
+                let Ok(signature) = p256::ecdsa::Signature::from_slice(signature) else {
+                    return Ok(false);
+                };
+                let message = format!("{}.{}", header, claims);
+                Ok(key
+                    .verifying_key()
+                    .verify(message.as_bytes(), &signature)
+                    .is_ok())
+            }
+        }
+    }
+}
+
+fn generate(algorithm: Algorithm) -> Result<SigningKey, Box<dyn std::error::Error>> {
+    match algorithm {
+        Algorithm::Hs256 => {
+            let mut key = vec![0u8; MIN_KEY_LENGTH];
+            OsRng.fill_bytes(&mut key);
+            Ok(SigningKey::hs256(&key))
+        }
+        Algorithm::Es256 => {
+            let pem = p256::ecdsa::SigningKey::random(&mut OsRng)
+                .to_pkcs8_pem(LineEnding::LF)
+                .map_err(|error| format!("Failed to generate ES256 key: {}", error))?;
+            SigningKey::parse(algorithm, pem.as_bytes())
+        }
+    }
+}
+
+/// Reads a signing key from the file, trailing line breaks are not a part of the key.
+pub fn read_from_file(
+    algorithm: Algorithm,
+    path: &Path,
+) -> Result<SigningKey, Box<dyn std::error::Error>> {
+    let mut key = std::fs::read(path)
+        .map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+    while matches!(key.last(), Some(b'\n' | b'\r')) {
+        key.pop();
+    }
+    SigningKey::parse(algorithm, &key)
+}
+
+/// Loads the signing key from the file if it is given, otherwise from the environment variable.
+///
+/// In the development mode a random key is generated when no key is configured,
+/// so tokens are not valid after a restart.
+pub fn load(
+    algorithm: Algorithm,
+    key_file: Option<&Path>,
+    dev: bool,
+) -> Result<SigningKey, Box<dyn std::error::Error>> {
+    if let Some(path) = key_file {
+        return read_from_file(algorithm, path);
+    }
+    if let Ok(key) = std::env::var(SIGNING_KEY_ENV) {
+        return SigningKey::parse(algorithm, key.as_bytes());
+    }
+    if dev {
+        return generate(algorithm);
+    }
+    Err(format!(
+        "No signing key configured, use --key-file, set {} or run with --dev",
+        SIGNING_KEY_ENV
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn write_key_file(name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("auction_house_{}_{}.key", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_key_from_file() {
+        let path = write_key_file("valid", &[KEY, b"\n"].concat());
+        let key = read_from_file(Algorithm::Hs256, &path).unwrap();
+        assert_eq!(key.id(), SigningKey::hs256(KEY).id());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_too_short_key_from_file() {
+        let path = write_key_file("short", b"secret");
+        assert!(read_from_file(Algorithm::Hs256, &path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_key_from_non_existing_file() {
+        let path = Path::new("/non/existing/file.key");
+        assert!(read_from_file(Algorithm::Hs256, path).is_err());
+    }
+
+    #[test]
+    fn test_read_es256_key_from_file() {
+        let pem = p256::ecdsa::SigningKey::random(&mut OsRng)
+            .to_pkcs8_pem(LineEnding::LF)
+            .unwrap();
+        let path = write_key_file("es256", pem.as_bytes());
+        let key = read_from_file(Algorithm::Es256, &path).unwrap();
+        assert!(key.public_key().is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_invalid_es256_key_from_file() {
+        let path = write_key_file("invalid_es256", KEY);
+        assert!(read_from_file(Algorithm::Es256, &path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_key_from_file_in_dev_mode() {
+        let path = write_key_file("dev", KEY);
+        let key = load(Algorithm::Hs256, Some(&path), true).unwrap();
+        assert_eq!(key.id(), SigningKey::hs256(KEY).id());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_keys() {
+        assert!(generate(Algorithm::Hs256).unwrap().public_key().is_none());
+        assert!(generate(Algorithm::Es256).unwrap().public_key().is_some());
+    }
+
+    #[test]
+    fn test_sign_and_verify_with_es256_key() {
+        let key = generate(Algorithm::Es256).unwrap();
+        let signature = key.sign("header", "claims").unwrap();
+        let signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        assert!(key.verify_bytes("header", "claims", &signature).unwrap());
+        assert!(!key
+            .verify_bytes("header", "other claims", &signature)
+            .unwrap());
+    }
+}
@@ -1,68 +1,478 @@
-use hmac::{Hmac, Mac};
-use jwt::{AlgorithmType, Header, SignWithKey, Token, VerifyWithKey};
-use sha2::Sha256;
-use std::collections::BTreeMap;
+use crate::revocation_list::{memory_storage::MemoryStorage, RevocationList};
+use crate::signing_key::{PublicKey, SigningKey};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use jwt::{Header, RegisteredClaims, SignWithKey, SigningAlgorithm, Token, VerifyWithKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(15 * 60);
+
+#[cfg(test)]
+pub const TEST_KEY: &[u8] = b"test key";
+
+struct SigningKeys {
+    active_key_id: String,
+    /// Verification keys by their ids, including the active key.
+    keys: BTreeMap<String, SigningKey>,
+    /// Times after which retired keys are no longer needed, in seconds since the Unix epoch.
+    retired: HashMap<String, u64>,
+}
 
 pub struct TokenBroker {
-    key: Hmac<Sha256>,
+    keys: RwLock<SigningKeys>,
+    lifetime: Duration,
+    revoked: Mutex<Box<dyn RevocationList + Send>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    user: String,
+    #[serde(flatten)]
+    registered: RegisteredClaims,
 }
 
-type TokenType<S> = Token<Header, BTreeMap<String, String>, S>;
+type TokenType<S> = Token<Header, Claims, S>;
+
+/// Information carried by a successfully verified token.
+#[derive(Debug, PartialEq)]
+pub struct VerifiedToken {
+    pub user: String,
+    /// Unique id of the token, the `jti` claim.
+    pub token_id: String,
+    /// Expiration time of the token in seconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TokenError {
+    Expired,
+    NotYetValid,
+    Revoked,
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenError::Expired => write!(f, "Token has expired"),
+            TokenError::NotYetValid => write!(f, "Token is not valid yet"),
+            TokenError::Revoked => write!(f, "Token has been revoked"),
+            TokenError::Invalid(reason) => write!(f, "Invalid token - {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+impl From<TokenError> for tonic::Status {
+    fn from(error: TokenError) -> Self {
+        let code = match error {
+            TokenError::Expired | TokenError::NotYetValid | TokenError::Revoked => {
+                tonic::Code::Unauthenticated
+            }
+            TokenError::Invalid(_) => tonic::Code::PermissionDenied,
+        };
+        tonic::Status::new(code, error.to_string())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn generate_token_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
 
 impl TokenBroker {
-    pub fn new() -> Self {
+    /// Creates a broker signing tokens with HS256 and the given secret.
+    #[cfg(test)]
+    pub fn new(key: &[u8]) -> Self {
+        Self::with_signing_key(SigningKey::hs256(key))
+    }
+
+    pub fn with_signing_key(key: SigningKey) -> Self {
+        let active_key_id = key.id().to_owned();
+        let keys = BTreeMap::from([(active_key_id.clone(), key)]);
         Self {
-            key: Hmac::new_from_slice(b"secret").unwrap(), // TODO: read a key from file
+            keys: RwLock::new(SigningKeys {
+                active_key_id,
+                keys,
+                retired: HashMap::new(),
+            }),
+            lifetime: DEFAULT_TOKEN_LIFETIME,
+            revoked: Mutex::new(Box::new(MemoryStorage::default())),
         }
     }
 
+    /// Sets for how long newly created tokens are valid.
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Makes the key active, the previously active key is used only to verify tokens
+    /// until all tokens signed with it expire.
+    ///
+    /// # Returns
+    /// Returns false if the key is already active.
+    pub fn rotate_key(&self, key: SigningKey) -> bool {
+        let new_key_id = key.id().to_owned();
+        let mut keys = self.keys.write().unwrap();
+        if keys.active_key_id == new_key_id {
+            return false;
+        }
+        let now = now();
+        let SigningKeys {
+            keys: verification_keys,
+            retired,
+            ..
+        } = &mut *keys;
+        retired.retain(|retired_key_id, retired_until| {
+            let keep = *retired_until > now;
+            if !keep {
+                verification_keys.remove(retired_key_id);
+            }
+            keep
+        });
+        let old_key_id = std::mem::replace(&mut keys.active_key_id, new_key_id.clone());
+        keys.retired
+            .insert(old_key_id, now + self.lifetime.as_secs());
+        keys.retired.remove(&new_key_id);
+        keys.keys.insert(new_key_id, key);
+        true
+    }
+
+    /// Returns public keys which can be used to verify tokens, empty if the tokens are signed with a symmetric key.
+    pub fn public_keys(&self) -> Vec<PublicKey> {
+        let keys = self.keys.read().unwrap();
+        let now = now();
+        keys.keys
+            .values()
+            .filter(|key| keys.retired.get(key.id()).is_none_or(|until| *until > now))
+            .filter_map(|key| key.public_key())
+            .collect()
+    }
+
+    /// Returns whether the token with the given id has been revoked.
+    pub fn is_revoked(&self, token_id: &str) -> bool {
+        self.revoked.lock().unwrap().is_revoked(token_id)
+    }
+
     pub fn create_new_token(&self, user: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let keys = self.keys.read().unwrap();
+        let key = &keys.keys[&keys.active_key_id];
         let header = Header {
-            algorithm: AlgorithmType::Hs256,
+            algorithm: key.algorithm_type(),
+            key_id: Some(keys.active_key_id.clone()),
             ..Default::default()
         };
-        let mut claims = BTreeMap::new();
-        claims.insert("user", user);
-        let token = Token::new(header, claims).sign_with_key(&self.key)?;
+        let issued_at = now();
+        let expires_at = issued_at + self.lifetime.as_secs();
+        let token_id = generate_token_id();
+        let claims = Claims {
+            user: user.to_owned(),
+            registered: RegisteredClaims {
+                expiration: Some(expires_at),
+                not_before: Some(issued_at),
+                issued_at: Some(issued_at),
+                json_web_token_id: Some(token_id.clone()),
+                ..Default::default()
+            },
+        };
+        let token = Token::new(header, claims).sign_with_key(key)?;
+        self.revoked
+            .lock()
+            .unwrap()
+            .add_token(user, &token_id, expires_at);
         Ok(token.as_str().to_owned())
     }
 
-    pub fn verify_token(&self, token_str: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let result: Result<TokenType<_>, _> = token_str.verify_with_key(&self.key);
-        if let Ok(token) = result {
-            if let Some(user) = token.claims().get("user") {
-                Ok(user.to_owned())
-            } else {
-                Err("Invalid token - user claim not found".into())
+    pub fn verify_token(&self, token_str: &str) -> Result<VerifiedToken, TokenError> {
+        let unverified: Result<TokenType<_>, _> = Token::parse_unverified(token_str);
+        let unverified = unverified.map_err(|_| TokenError::Invalid("verification has failed"))?;
+        let Some(key_id) = unverified.header().key_id.as_deref() else {
+            return Err(TokenError::Invalid("key id not found"));
+        };
+        let result: Result<TokenType<_>, _> = {
+            let keys = self.keys.read().unwrap();
+            let retired_until = keys.retired.get(key_id).copied().unwrap_or(u64::MAX);
+            match keys.keys.get(key_id) {
+                Some(key) if retired_until > now() => token_str.verify_with_key(key),
+                _ => return Err(TokenError::Invalid("unknown signing key")),
             }
-        } else {
-            Err("Invalid token - verification has failed".into())
+        };
+        let token = result.map_err(|_| TokenError::Invalid("verification has failed"))?;
+        let claims = token.claims();
+        let (Some(expires_at), Some(not_before), Some(token_id)) = (
+            claims.registered.expiration,
+            claims.registered.not_before,
+            claims.registered.json_web_token_id.clone(),
+        ) else {
+            return Err(TokenError::Invalid("registered claims not found"));
+        };
+        let now = now();
+        if now >= expires_at {
+            return Err(TokenError::Expired);
         }
+        if now < not_before {
+            return Err(TokenError::NotYetValid);
+        }
+        if self.revoked.lock().unwrap().is_revoked(&token_id) {
+            return Err(TokenError::Revoked);
+        }
+        Ok(VerifiedToken {
+            user: claims.user.clone(),
+            token_id,
+            expires_at,
+        })
+    }
+
+    /// Invalidates the token, it is rejected by all further verifications.
+    pub fn revoke_token(&self, token: &VerifiedToken) {
+        self.revoked
+            .lock()
+            .unwrap()
+            .revoke_token(&token.user, &token.token_id, token.expires_at);
+    }
+
+    /// Invalidates all tokens issued to the user so far.
+    pub fn revoke_user_tokens(&self, user: &str) {
+        self.revoked.lock().unwrap().revoke_user_tokens(user);
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::signing_key::Algorithm;
+    use p256::pkcs8::{EncodePrivateKey, LineEnding};
+
+    const OTHER_KEY: &[u8] = b"other test key";
+
+    fn sign(claims: Claims, key: &[u8]) -> String {
+        let key = SigningKey::hs256(key);
+        let header = Header {
+            algorithm: key.algorithm_type(),
+            key_id: Some(key.id().to_owned()),
+            ..Default::default()
+        };
+        Token::new(header, claims)
+            .sign_with_key(&key)
+            .unwrap()
+            .as_str()
+            .to_owned()
+    }
     #[test]
     fn test_create_new_token() {
-        let engine = TokenBroker::new();
+        let engine = TokenBroker::new(TEST_KEY);
         let token_str = engine.create_new_token("user").unwrap();
-        let token: TokenType<_> = token_str.verify_with_key(&engine.key).unwrap();
-        assert_eq!(token.claims().get("user").unwrap(), "user");
+        let key = SigningKey::hs256(TEST_KEY);
+        let token: TokenType<_> = token_str.verify_with_key(&key).unwrap();
+        assert_eq!(token.header().key_id.as_deref(), Some(key.id()));
+        let claims = token.claims();
+        assert_eq!(claims.user, "user");
+        let issued_at = claims.registered.issued_at.unwrap();
+        assert_eq!(claims.registered.not_before, Some(issued_at));
+        assert_eq!(
+            claims.registered.expiration,
+            Some(issued_at + DEFAULT_TOKEN_LIFETIME.as_secs())
+        );
+        assert!(claims.registered.json_web_token_id.is_some());
+    }
+
+    #[test]
+    fn test_create_tokens_with_unique_ids() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let token1 = engine.verify_token(&engine.create_new_token("user").unwrap());
+        let token2 = engine.verify_token(&engine.create_new_token("user").unwrap());
+        assert_ne!(token1.unwrap().token_id, token2.unwrap().token_id);
     }
 
     #[test]
     fn test_verify_token() {
-        let engine = TokenBroker::new();
+        let engine = TokenBroker::new(TEST_KEY);
         let token_str = engine.create_new_token("user").unwrap();
-        assert_eq!(engine.verify_token(&token_str).unwrap(), "user");
+        let token = engine.verify_token(&token_str).unwrap();
+        assert_eq!(token.user, "user");
     }
 
     #[test]
     fn test_verify_invalid_token() {
-        let engine = TokenBroker::new();
+        let engine = TokenBroker::new(TEST_KEY);
         let token = "invalid token".to_owned();
-        assert!(engine.verify_token(&token).is_err());
+        assert!(matches!(
+            engine.verify_token(&token),
+            Err(TokenError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_expired_token() {
+        let engine = TokenBroker::new(TEST_KEY).with_lifetime(Duration::from_secs(0));
+        let token_str = engine.create_new_token("user").unwrap();
+        assert_eq!(engine.verify_token(&token_str), Err(TokenError::Expired));
+    }
+
+    #[test]
+    fn test_verify_revoked_token() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let token_str = engine.create_new_token("user").unwrap();
+        let token = engine.verify_token(&token_str).unwrap();
+        engine.revoke_token(&token);
+        assert_eq!(engine.verify_token(&token_str), Err(TokenError::Revoked));
+    }
+
+    #[test]
+    fn test_verify_tokens_after_revoking_user_tokens() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let token_str1 = engine.create_new_token("user").unwrap();
+        let token_str2 = engine.create_new_token("user").unwrap();
+        let other_token_str = engine.create_new_token("other user").unwrap();
+        engine.revoke_user_tokens("user");
+        assert_eq!(engine.verify_token(&token_str1), Err(TokenError::Revoked));
+        assert_eq!(engine.verify_token(&token_str2), Err(TokenError::Revoked));
+        assert!(engine.verify_token(&other_token_str).is_ok());
+        let new_token_str = engine.create_new_token("user").unwrap();
+        assert!(engine.verify_token(&new_token_str).is_ok());
+    }
+
+    #[test]
+    fn test_verify_not_yet_valid_token() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let claims = Claims {
+            user: "user".to_owned(),
+            registered: RegisteredClaims {
+                expiration: Some(now() + 200),
+                not_before: Some(now() + 100),
+                issued_at: Some(now()),
+                json_web_token_id: Some(generate_token_id()),
+                ..Default::default()
+            },
+        };
+        let token_str = sign(claims, TEST_KEY);
+        assert_eq!(
+            engine.verify_token(&token_str),
+            Err(TokenError::NotYetValid)
+        );
+    }
+
+    #[test]
+    fn test_verify_token_without_expiration() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let claims = Claims {
+            user: "user".to_owned(),
+            registered: RegisteredClaims::default(),
+        };
+        let token_str = sign(claims, TEST_KEY);
+        assert!(matches!(
+            engine.verify_token(&token_str),
+            Err(TokenError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_token_signed_with_unknown_key() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let other_engine = TokenBroker::new(OTHER_KEY);
+        let token_str = other_engine.create_new_token("user").unwrap();
+        assert!(matches!(
+            engine.verify_token(&token_str),
+            Err(TokenError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_token_without_key_id() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let claims = Claims {
+            user: "user".to_owned(),
+            registered: RegisteredClaims {
+                expiration: Some(now() + 100),
+                not_before: Some(now()),
+                issued_at: Some(now()),
+                json_web_token_id: Some(generate_token_id()),
+                ..Default::default()
+            },
+        };
+        let token_str = claims.sign_with_key(&SigningKey::hs256(TEST_KEY)).unwrap();
+        assert!(matches!(
+            engine.verify_token(&token_str),
+            Err(TokenError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_rotate_key() {
+        let engine = TokenBroker::new(TEST_KEY);
+        let old_token_str = engine.create_new_token("user").unwrap();
+        assert!(engine.rotate_key(SigningKey::hs256(OTHER_KEY)));
+        let new_token_str = engine.create_new_token("user").unwrap();
+        assert!(engine.verify_token(&old_token_str).is_ok());
+        assert!(engine.verify_token(&new_token_str).is_ok());
+        let new_token: TokenType<_> = Token::parse_unverified(&new_token_str).unwrap();
+        assert_eq!(
+            new_token.header().key_id.as_deref(),
+            Some(SigningKey::hs256(OTHER_KEY).id())
+        );
+    }
+
+    #[test]
+    fn test_rotate_to_active_key() {
+        let engine = TokenBroker::new(TEST_KEY);
+        assert!(!engine.rotate_key(SigningKey::hs256(TEST_KEY)));
+    }
+
+    #[test]
+    fn test_retired_key_is_dropped_after_tokens_expire() {
+        let engine = TokenBroker::new(TEST_KEY).with_lifetime(Duration::from_secs(0));
+        let claims = Claims {
+            user: "user".to_owned(),
+            registered: RegisteredClaims {
+                expiration: Some(now() + 100),
+                not_before: Some(now()),
+                issued_at: Some(now()),
+                json_web_token_id: Some(generate_token_id()),
+                ..Default::default()
+            },
+        };
+        let token_str = sign(claims, TEST_KEY);
+        assert!(engine.rotate_key(SigningKey::hs256(OTHER_KEY)));
+        assert!(matches!(
+            engine.verify_token(&token_str),
+            Err(TokenError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_and_verify_es256_token() {
+        let pem = p256::ecdsa::SigningKey::random(&mut OsRng)
+            .to_pkcs8_pem(LineEnding::LF)
+            .unwrap();
+        let key = SigningKey::parse(Algorithm::Es256, pem.as_bytes()).unwrap();
+        let engine = TokenBroker::with_signing_key(key);
+        let token_str = engine.create_new_token("user").unwrap();
+        let token: TokenType<_> = Token::parse_unverified(&token_str).unwrap();
+        assert_eq!(token.header().algorithm, jwt::AlgorithmType::Es256);
+        assert_eq!(engine.verify_token(&token_str).unwrap().user, "user");
+        let public_keys = engine.public_keys();
+        assert_eq!(public_keys.len(), 1);
+        assert_eq!(
+            Some(public_keys[0].key_id.as_str()),
+            token.header().key_id.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_hs256_broker_has_no_public_keys() {
+        let engine = TokenBroker::new(TEST_KEY);
+        assert!(engine.public_keys().is_empty());
     }
 }
@@ -2,7 +2,10 @@ use crate::token_engine::TokenBroker;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 use verifier_proto::token_verifier_server::{TokenVerifier, TokenVerifierServer};
-use verifier_proto::TokenRequest;
+use verifier_proto::{
+    PublicKey, PublicKeysResponse, TokenIdRequest, TokenRequest, TokenRevokedResponse,
+    VerifyTokenResponse,
+};
 
 pub mod verifier_proto {
     tonic::include_proto!("auction_house_rs.session.token_verifier");
@@ -26,38 +29,115 @@ impl TokenVerifierService {
 
 #[tonic::async_trait]
 impl TokenVerifier for TokenVerifierService {
-    async fn verify_token(&self, request: Request<TokenRequest>) -> Result<Response<()>, Status> {
+    async fn verify_token(
+        &self,
+        request: Request<TokenRequest>,
+    ) -> Result<Response<VerifyTokenResponse>, Status> {
         let token = request.into_inner().token;
-        if let Err(_) = self.tokens.verify_token(&token) {
-            return Err(Status::new(
-                tonic::Code::PermissionDenied,
-                "Invalid token".to_string(),
-            ));
-        }
-        Ok(Response::new(()))
+        let verified = self.tokens.verify_token(&token)?;
+        Ok(Response::new(VerifyTokenResponse {
+            username: verified.user,
+            expires_at: verified.expires_at,
+            token_id: verified.token_id,
+        }))
+    }
+
+    async fn get_public_keys(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<PublicKeysResponse>, Status> {
+        let keys = self
+            .tokens
+            .public_keys()
+            .into_iter()
+            .map(|key| PublicKey {
+                kid: key.key_id,
+                kty: "EC".to_string(),
+                alg: key.algorithm,
+                crv: key.curve,
+                x: key.x,
+                y: key.y,
+            })
+            .collect();
+        Ok(Response::new(PublicKeysResponse { keys }))
+    }
+
+    async fn is_token_revoked(
+        &self,
+        request: Request<TokenIdRequest>,
+    ) -> Result<Response<TokenRevokedResponse>, Status> {
+        let revoked = self.tokens.is_revoked(&request.into_inner().token_id);
+        Ok(Response::new(TokenRevokedResponse { revoked }))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::token_engine::TEST_KEY;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_verify_token() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = TokenVerifierService::new(tokens.clone());
         let token_str = tokens.create_new_token("user").unwrap();
+        let verified = tokens.verify_token(&token_str).unwrap();
         let request = Request::new(TokenRequest { token: token_str });
-        assert!(service.verify_token(request).await.is_ok());
+        let response = service.verify_token(request).await.unwrap().into_inner();
+        assert_eq!(response.username, "user");
+        assert_eq!(response.expires_at, verified.expires_at);
+        assert_eq!(response.token_id, verified.token_id);
     }
 
     #[tokio::test]
     async fn test_verify_invalid_token() {
-        let tokens = Arc::new(TokenBroker::new());
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
         let service = TokenVerifierService::new(tokens.clone());
         let request = Request::new(TokenRequest {
             token: "invalid token".to_string(),
         });
-        assert!(service.verify_token(request).await.is_err());
+        let status = service.verify_token(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_verify_expired_token() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY).with_lifetime(Duration::from_secs(0)));
+        let service = TokenVerifierService::new(tokens.clone());
+        let token_str = tokens.create_new_token("user").unwrap();
+        let request = Request::new(TokenRequest { token: token_str });
+        let status = service.verify_token(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_is_token_revoked() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = TokenVerifierService::new(tokens.clone());
+        let token_str = tokens.create_new_token("user").unwrap();
+        let verified = tokens.verify_token(&token_str).unwrap();
+        let request = || {
+            Request::new(TokenIdRequest {
+                token_id: verified.token_id.clone(),
+            })
+        };
+        let response = service.is_token_revoked(request()).await.unwrap();
+        assert!(!response.into_inner().revoked);
+        tokens.revoke_token(&verified);
+        let response = service.is_token_revoked(request()).await.unwrap();
+        assert!(response.into_inner().revoked);
+    }
+
+    #[tokio::test]
+    async fn test_get_public_keys_of_hs256_tokens() {
+        let tokens = Arc::new(TokenBroker::new(TEST_KEY));
+        let service = TokenVerifierService::new(tokens.clone());
+        let response = service
+            .get_public_keys(Request::new(()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.keys.is_empty());
     }
 }
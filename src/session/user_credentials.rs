@@ -1,12 +1,67 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
 pub mod memory_storage;
+pub mod mongo_storage;
+
+/// Storage of users' credentials, shared between requests, so it has to synchronize access internally.
+#[tonic::async_trait]
+pub trait UserCredentials: Send + Sync {
+    async fn add_user(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-pub trait UserCredentials {
-    fn add_user(&mut self, user: &str, password: &str) -> Result<(), Box<dyn std::error::Error>>;
+    async fn update_user(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-    fn update_user(&mut self, user: &str, password: &str)
-        -> Result<(), Box<dyn std::error::Error>>;
+    async fn verify_user(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-    fn verify_user(&self, user: &str, password: &str) -> Result<(), Box<dyn std::error::Error>>;
+    async fn remove_user(&self, user: &str)
+        -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Hashes the password on a blocking thread, Argon2 is too slow to run on the async runtime.
+async fn hash_password(password: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let password = password.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        match Argon2::default().hash_password(password.as_bytes(), &salt) {
+            Ok(hash) => Ok(hash.to_string()),
+            Err(_) => Err("Failed to hash password".into()),
+        }
+    })
+    .await?
+}
 
-    fn remove_user(&mut self, user: &str);
+/// Verifies the password on a blocking thread, Argon2 is too slow to run on the async runtime.
+async fn verify_password(
+    hash: &str,
+    password: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let hash = hash.to_owned();
+    let password = password.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let Ok(hash) = PasswordHash::new(&hash) else {
+            return Err("Failed to get hash password".into());
+        };
+        if Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_err()
+        {
+            return Err("Wrong username or password".into());
+        }
+        Ok(())
+    })
+    .await?
 }
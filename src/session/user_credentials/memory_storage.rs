@@ -1,68 +1,70 @@
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
-};
-
+use super::{hash_password, verify_password};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
 
+/// Stores credentials in memory, passwords are hashed without holding the lock.
 #[derive(Default)]
 pub struct MemoryStorage {
-    users: HashMap<String, String>,
+    users: Mutex<HashMap<String, String>>,
 }
 
+#[tonic::async_trait]
 impl super::UserCredentials for MemoryStorage {
-    fn add_user(&mut self, user: &str, password: &str) -> Result<(), Box<dyn std::error::Error>> {
-        if let None = self.users.get(user) {
-            let salt = SaltString::generate(&mut OsRng);
-            let hash = Argon2::default().hash_password(password.as_bytes(), &salt);
-            if hash.is_err() {
-                return Err("Failed to hash password".into());
+    async fn add_user(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.users.lock().unwrap().contains_key(user) {
+            return Err("User already exists".into());
+        }
+        let hash = hash_password(password).await?;
+        // the user may have been added while the password was being hashed
+        match self.users.lock().unwrap().entry(user.to_owned()) {
+            Entry::Occupied(_) => Err("User already exists".into()),
+            Entry::Vacant(entry) => {
+                entry.insert(hash);
+                Ok(())
             }
-            self.users
-                .insert(user.to_owned(), hash.unwrap().to_string());
-            Ok(())
-        } else {
-            Err("User already exists".into())
         }
     }
 
-    fn update_user(
-        &mut self,
+    async fn update_user(
+        &self,
         user: &str,
         password: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(_) = self.users.get(user) {
-            let salt = SaltString::generate(&mut OsRng);
-            let hash = Argon2::default().hash_password(password.as_bytes(), &salt);
-            if hash.is_err() {
-                return Err("Failed to hash password".into());
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self.users.lock().unwrap().contains_key(user) {
+            return Err("User does not exist".into());
+        }
+        let hash = hash_password(password).await?;
+        // the user may have been removed while the password was being hashed
+        match self.users.lock().unwrap().get_mut(user) {
+            Some(stored) => {
+                *stored = hash;
+                Ok(())
             }
-            self.users
-                .insert(user.to_owned(), hash.unwrap().to_string());
-            Ok(())
-        } else {
-            Err("User does not exist".into())
+            None => Err("User does not exist".into()),
         }
     }
 
-    fn verify_user(&self, user: &str, password: &str) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(hash_str) = self.users.get(user) {
-            let hash = PasswordHash::new(&hash_str);
-            if hash.is_err() {
-                return Err("Failed to get hash password".into());
-            }
-            let result = Argon2::default().verify_password(password.as_bytes(), &hash.unwrap());
-            if result.is_err() {
-                return Err("Wrong username or password".into());
-            }
-            Ok(())
-        } else {
-            Err("Wrong username or password".into())
+    async fn verify_user(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let hash = self.users.lock().unwrap().get(user).cloned();
+        match hash {
+            Some(hash) => verify_password(&hash, password).await,
+            None => Err("Wrong username or password".into()),
         }
     }
 
-    fn remove_user(&mut self, user: &str) {
-        self.users.remove(user);
+    async fn remove_user(&self, user: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.users.lock().unwrap().remove(user);
+        Ok(())
     }
 }
 
@@ -71,43 +73,53 @@ mod test {
     use super::*;
     use crate::user_credentials::UserCredentials;
 
-    #[test]
-    fn add_user_and_verify_its_password() {
-        let mut users = MemoryStorage::default();
+    #[tokio::test]
+    async fn add_user_and_verify_its_password() {
+        let users = MemoryStorage::default();
         let user = "user";
         let password = "password";
-        users.add_user(user, password).unwrap();
-        assert!(users.verify_user(user, password).is_ok());
+        users.add_user(user, password).await.unwrap();
+        assert!(users.verify_user(user, password).await.is_ok());
     }
 
-    #[test]
-    fn update_user_and_verify_its_password() {
-        let mut users = MemoryStorage::default();
+    #[tokio::test]
+    async fn update_user_and_verify_its_password() {
+        let users = MemoryStorage::default();
         let user = "user";
         let password = "password";
-        users.add_user(user, password).unwrap();
-        assert!(users.verify_user(user, password).is_ok());
+        users.add_user(user, password).await.unwrap();
+        assert!(users.verify_user(user, password).await.is_ok());
         let new_password = "new password";
-        users.update_user(user, new_password).unwrap();
-        assert!(users.verify_user(user, new_password).is_ok());
+        users.update_user(user, new_password).await.unwrap();
+        assert!(users.verify_user(user, new_password).await.is_ok());
     }
 
-    #[test]
-    fn add_user_remove_it_and_expect_verification_to_fail() {
-        let mut users = MemoryStorage::default();
+    #[tokio::test]
+    async fn add_user_remove_it_and_expect_verification_to_fail() {
+        let users = MemoryStorage::default();
         let user = "user";
         let password = "password";
-        users.add_user(user, password).unwrap();
-        users.remove_user(user);
-        assert!(users.verify_user(user, password).is_err());
+        users.add_user(user, password).await.unwrap();
+        users.remove_user(user).await.unwrap();
+        assert!(users.verify_user(user, password).await.is_err());
     }
 
-    #[test]
-    fn add_user_and_verify_wrong_password() {
-        let mut users = MemoryStorage::default();
+    #[tokio::test]
+    async fn add_user_and_verify_wrong_password() {
+        let users = MemoryStorage::default();
         let user = "user";
         let password = "password";
-        users.add_user(user, password).unwrap();
-        assert!(users.verify_user(user, "wrong password").is_err());
+        users.add_user(user, password).await.unwrap();
+        assert!(users.verify_user(user, "wrong password").await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn add_same_user_concurrently_and_expect_one_to_succeed() {
+        let users = MemoryStorage::default();
+        let (first, second) = tokio::join!(
+            users.add_user("user", "password"),
+            users.add_user("user", "other password"),
+        );
+        assert!(first.is_ok() != second.is_ok());
     }
 }